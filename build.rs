@@ -1,4 +1,5 @@
 use std::env;
+use std::path::PathBuf;
 
 fn main() {
     let include_path = if let Ok(path) = env::var("PROTOC_INCLUDE") {
@@ -13,7 +14,11 @@ fn main() {
         "/usr/include".to_string()
     };
 
+    // Descriptor set served by the reflection service
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+
     tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("reservations_descriptor.bin"))
         .compile(
             &["proto/reservations.proto"],
             &[include_path.as_str(), "proto"],
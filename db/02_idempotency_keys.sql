@@ -0,0 +1,7 @@
+-- Let clients safely retry reservation creation
+
+-- Key supplied by the client, cleared once it falls outside the retry window
+ALTER TABLE reservations ADD COLUMN idempotency_key TEXT;
+
+-- Keys are scoped per client
+CREATE UNIQUE INDEX idx_reservations_idempotency_key ON reservations(client_id, idempotency_key);
@@ -0,0 +1,6 @@
+-- Link the occurrences of a recurring reservation together
+
+ALTER TABLE reservations ADD COLUMN series_id UUID;
+
+-- Create index for finding every occurrence in a series
+CREATE INDEX idx_reservations_series_id ON reservations(series_id);
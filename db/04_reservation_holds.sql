@@ -0,0 +1,18 @@
+-- Support tentative holds that expire unless they are confirmed
+
+-- When a pending hold stops blocking its time slot
+ALTER TABLE reservations ADD COLUMN expires_at TIMESTAMPTZ;
+
+-- Restrict status to the known values
+ALTER TABLE reservations ADD CONSTRAINT valid_status
+    CHECK (status IN ('confirmed', 'cancelled', 'pending', 'expired'));
+
+-- Every hold needs an expiry
+ALTER TABLE reservations ADD CONSTRAINT pending_has_expiry
+    CHECK (status <> 'pending' OR expires_at IS NOT NULL);
+
+-- Only reservations that hold their time slot take part in overlap detection
+ALTER TABLE reservations DROP CONSTRAINT no_overlapping_reservations;
+ALTER TABLE reservations ADD CONSTRAINT no_overlapping_reservations EXCLUDE USING gist (
+    tstzrange(start_time, end_time) WITH &&
+) WHERE (status IN ('confirmed', 'pending'));
@@ -0,0 +1,3 @@
+-- Record why a reservation was cancelled
+
+ALTER TABLE reservations ADD COLUMN cancellation_reason TEXT;
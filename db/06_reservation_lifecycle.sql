@@ -0,0 +1,5 @@
+-- Track what happened to a reservation once its time has passed
+
+ALTER TABLE reservations DROP CONSTRAINT valid_status;
+ALTER TABLE reservations ADD CONSTRAINT valid_status
+    CHECK (status IN ('confirmed', 'cancelled', 'pending', 'expired', 'completed', 'no_show'));
@@ -0,0 +1,3 @@
+-- Record when the client arrived for a reservation
+
+ALTER TABLE reservations ADD COLUMN checked_in_at TIMESTAMPTZ;
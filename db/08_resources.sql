@@ -0,0 +1,22 @@
+-- Bookable resources, e.g. courts or rooms, each holding up to `capacity` overlapping reservations
+
+CREATE TABLE resources (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    capacity INTEGER NOT NULL DEFAULT 1 CHECK (capacity > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Reservations made before resources existed belong to a single default resource
+INSERT INTO resources (id, name, capacity)
+VALUES ('00000000-0000-0000-0000-000000000000', 'default', 1);
+
+ALTER TABLE reservations
+    ADD COLUMN resource_id UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000000'
+    REFERENCES resources(id);
+
+CREATE INDEX idx_reservations_resource_time ON reservations (resource_id, start_time, end_time);
+
+-- Capacity can't be expressed as an exclusion constraint, bookings lock the resource row
+-- and count overlapping reservations instead
+ALTER TABLE reservations DROP CONSTRAINT no_overlapping_reservations;
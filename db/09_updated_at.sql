@@ -0,0 +1,18 @@
+-- Track when each reservation was last modified
+
+ALTER TABLE reservations ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+UPDATE reservations SET updated_at = created_at;
+
+-- Bump updated_at on every update so no mutating query can forget to
+CREATE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reservations_set_updated_at
+    BEFORE UPDATE ON reservations
+    FOR EACH ROW
+    EXECUTE FUNCTION set_updated_at();
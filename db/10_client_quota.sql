@@ -0,0 +1,3 @@
+-- Per-client override of how many active reservations a client may hold, NULL uses the default
+
+ALTER TABLE clients ADD COLUMN reservation_quota INTEGER CHECK (reservation_quota >= 0);
@@ -0,0 +1,10 @@
+-- Clients sharing a reservation with its owner, e.g. the students of a group lesson
+
+CREATE TABLE reservation_attendees (
+    reservation_id UUID NOT NULL REFERENCES reservations(id) ON DELETE CASCADE,
+    client_id UUID NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    PRIMARY KEY (reservation_id, client_id)
+);
+
+-- Find the reservations a client attends
+CREATE INDEX idx_reservation_attendees_client_id ON reservation_attendees(client_id);
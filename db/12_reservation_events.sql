@@ -0,0 +1,27 @@
+-- Audit trail of reservation status changes, rows are only ever added
+
+CREATE TABLE reservation_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reservation_id UUID NOT NULL REFERENCES reservations(id) ON DELETE CASCADE,
+    -- NULL for the event recording the reservation's creation
+    old_status TEXT,
+    new_status TEXT NOT NULL,
+    -- Who made the change, when the caller identified themselves
+    actor TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_reservation_events_reservation_id
+    ON reservation_events (reservation_id, created_at);
+
+-- Events are immutable, they are only removed along with their reservation
+CREATE FUNCTION reject_reservation_event_update() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'reservation_events rows cannot be modified';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reservation_events_immutable
+    BEFORE UPDATE ON reservation_events
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_reservation_event_update();
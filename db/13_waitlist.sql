@@ -0,0 +1,17 @@
+-- Clients waiting for a slot on a resource to free up, promoted oldest first on cancellation
+
+CREATE TABLE waitlist (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    client_id UUID NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    resource_id UUID NOT NULL REFERENCES resources(id),
+    start_time TIMESTAMPTZ NOT NULL,
+    end_time TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT valid_waitlist_time_range CHECK (end_time > start_time)
+);
+
+-- Find the oldest entries wanting a freed slot
+CREATE INDEX idx_waitlist_resource_time ON waitlist (resource_id, start_time, end_time, created_at);
+
+CREATE INDEX idx_waitlist_client_id ON waitlist(client_id);
@@ -0,0 +1,24 @@
+-- Announce every reservation change on the reservation_changes channel once it commits, as
+-- "<kind>:<reservation id>" where kind is created, cancelled or updated
+
+CREATE FUNCTION notify_reservation_change() RETURNS TRIGGER AS $$
+DECLARE
+    kind TEXT;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        kind := 'created';
+    ELSIF NEW.status = 'cancelled' AND OLD.status <> 'cancelled' THEN
+        kind := 'cancelled';
+    ELSE
+        kind := 'updated';
+    END IF;
+
+    PERFORM pg_notify('reservation_changes', kind || ':' || NEW.id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reservations_notify_change
+    AFTER INSERT OR UPDATE ON reservations
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_reservation_change();
@@ -0,0 +1,3 @@
+-- Resources taken out of service keep their reservations but accept no new bookings
+
+ALTER TABLE resources ADD COLUMN active BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,28 @@
+-- Short codes customers can read out instead of the reservation UUID, e.g. R-7QF4K2
+
+-- Digits and letters that are easily confused (0/O, 1/I) are left out
+CREATE FUNCTION generate_reservation_reference() RETURNS TEXT AS $$
+DECLARE
+    alphabet CONSTANT TEXT := '23456789ABCDEFGHJKLMNPQRSTUVWXYZ';
+    code TEXT;
+BEGIN
+    LOOP
+        code := 'R-';
+        FOR i IN 1..6 LOOP
+            code := code || substr(alphabet, 1 + floor(random() * length(alphabet))::INT, 1);
+        END LOOP;
+        EXIT WHEN NOT EXISTS (SELECT 1 FROM reservations WHERE reference = code);
+    END LOOP;
+    RETURN code;
+END;
+$$ LANGUAGE plpgsql VOLATILE;
+
+ALTER TABLE reservations ADD COLUMN reference TEXT;
+
+UPDATE reservations SET reference = generate_reservation_reference();
+
+ALTER TABLE reservations
+    ALTER COLUMN reference SET DEFAULT generate_reservation_reference(),
+    ALTER COLUMN reference SET NOT NULL;
+
+CREATE UNIQUE INDEX idx_reservations_reference ON reservations (reference);
@@ -0,0 +1,16 @@
+-- Version number for optimistic concurrency control, callers pass the version they last saw
+
+ALTER TABLE reservations ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- Bump the version on every update so a caller holding an older copy can be told
+CREATE FUNCTION bump_reservation_version() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reservations_bump_version
+    BEFORE UPDATE ON reservations
+    FOR EACH ROW
+    EXECUTE FUNCTION bump_reservation_version();
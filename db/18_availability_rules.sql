@@ -0,0 +1,10 @@
+-- Opening hours per day of the week, slots and optionally bookings must fall within them
+
+CREATE TABLE availability_rules (
+    -- ISO day of the week, 1 is Monday and 7 is Sunday
+    day_of_week SMALLINT NOT NULL CHECK (day_of_week BETWEEN 1 AND 7),
+    open_time TIME NOT NULL,
+    -- A close time at or before the open time means closing the following day
+    close_time TIME NOT NULL,
+    PRIMARY KEY (day_of_week, open_time)
+);
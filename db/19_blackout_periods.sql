@@ -0,0 +1,15 @@
+-- Periods such as holidays or maintenance during which nothing can be booked
+
+CREATE TABLE blackout_periods (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- NULL blocks every resource
+    resource_id UUID REFERENCES resources(id) ON DELETE CASCADE,
+    start_time TIMESTAMPTZ NOT NULL,
+    end_time TIMESTAMPTZ NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (start_time < end_time)
+);
+
+CREATE INDEX idx_blackout_periods_range
+    ON blackout_periods USING gist (tstzrange(start_time, end_time));
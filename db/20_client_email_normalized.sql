@@ -0,0 +1,32 @@
+-- Emails are stored trimmed and lowercased, and must be unique in that form
+
+-- Clients whose emails only differ by case or surrounding whitespace can't be told apart
+-- any more, so stop and name them rather than pick which one keeps the address
+DO $$
+DECLARE
+    duplicates TEXT;
+BEGIN
+    SELECT string_agg(format('%s (clients %s)', email, ids), '; ' ORDER BY email)
+    INTO duplicates
+    FROM (
+        SELECT lower(btrim(email)) AS email,
+               string_agg(id::text, ', ' ORDER BY created_at, id) AS ids
+        FROM clients
+        GROUP BY lower(btrim(email))
+        HAVING COUNT(*) > 1
+    ) AS duplicated;
+
+    IF duplicates IS NOT NULL THEN
+        RAISE EXCEPTION 'Client emails collide once trimmed and lowercased, merge or change them before migrating: %',
+            duplicates;
+    END IF;
+END
+$$;
+
+UPDATE clients SET email = lower(btrim(email)) WHERE email <> lower(btrim(email));
+
+ALTER TABLE clients
+    ADD COLUMN email_normalized TEXT GENERATED ALWAYS AS (lower(btrim(email))) STORED;
+
+ALTER TABLE clients
+    ADD CONSTRAINT clients_email_normalized_key UNIQUE (email_normalized);
@@ -0,0 +1,7 @@
+-- Trigram indexes so substring searches on client names and emails don't scan every client
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_clients_name_trgm ON clients USING gin (name gin_trgm_ops);
+
+CREATE INDEX idx_clients_email_trgm ON clients USING gin (email gin_trgm_ops);
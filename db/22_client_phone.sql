@@ -0,0 +1,3 @@
+-- Phone number for reminder calls, in international format, NULL when not given
+
+ALTER TABLE clients ADD COLUMN phone TEXT;
@@ -0,0 +1,3 @@
+-- Archived clients are hidden from client listings and can't book, their reservations are kept
+
+ALTER TABLE clients ADD COLUMN archived_at TIMESTAMPTZ;
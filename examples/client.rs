@@ -7,7 +7,10 @@ pub mod proto {
 }
 
 use proto::reservation_service_client::ReservationServiceClient;
-use proto::{ClientId, ClientRequest, ReservationId, ReservationRequest, TimeRange};
+use proto::{
+    CancelReservationRequest, ClientRequest, CountReservationsRequest,
+    ListClientReservationsRequest, ReservationId, ReservationRequest, TimeRange,
+};
 
 fn datetime_to_timestamp(dt: &chrono::DateTime<Utc>) -> Timestamp {
     Timestamp {
@@ -33,35 +36,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = ReservationServiceClient::connect("http://[::1]:50051").await?;
 
     println!("\n--- Setting up client ---");
-    // First check if there is already an existing client
-    let response = client.list_clients(Request::new(())).await?;
-    let clients = response.into_inner().clients;
-    let mut client_id = String::new();
-    for client in clients {
-        if client.name == NAME {
-            println!(
-                "Found existing client: ID={}, Name={}",
-                client.id, client.name
-            );
-            client_id = client.id.clone();
-            break;
-        }
-    }
-
-    if client_id.is_empty() {
-        let client_request = Request::new(ClientRequest {
+    // Reuse the client from a previous run if there is one
+    let response = client
+        .get_or_create_client(Request::new(ClientRequest {
             name: NAME.to_string(),
             email: EMAIL.to_string(),
-        });
-
-        let response = client.create_client(client_request).await?;
-        let client_info = response.into_inner();
-        client_id = client_info.id.clone();
-        println!(
-            "Created client: ID={}, Name={}, Email={}",
-            client_info.id, client_info.name, client_info.email
-        );
-    }
+            ..Default::default()
+        }))
+        .await?;
+    let client_info = response.into_inner();
+    let client_id = client_info.id.clone();
+    println!(
+        "Using client: ID={}, Name={}, Email={}",
+        client_info.id, client_info.name, client_info.email
+    );
 
     // List available slots
     let now = Utc::now();
@@ -71,6 +59,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let request = Request::new(TimeRange {
         start_time: Some(datetime_to_timestamp(&now)),
         end_time: Some(datetime_to_timestamp(&tomorrow)),
+        ..Default::default()
     });
 
     let response = client.list_available_slots(request).await?;
@@ -87,6 +76,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             client_id: client_id.clone(),
             slot: Some(slot),
             notes: "Example reservation".to_string(),
+            ..Default::default()
         });
 
         let response = match client.create_reservation(request).await {
@@ -146,14 +136,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // List client reservations
         println!("\n--- Listing client reservations ---");
-        let request = Request::new(ClientId {
-            id: client_id.clone(),
+        let request = Request::new(ListClientReservationsRequest {
+            client_id: client_id.clone(),
+            ..Default::default()
         });
 
         let response = client.list_client_reservations(request).await?;
         let reservations = response.into_inner().reservations;
 
-        println!("Client has {} reservations:", reservations.len());
+        let request = Request::new(CountReservationsRequest {
+            client_id: client_id.clone(),
+            ..Default::default()
+        });
+        let count = client.count_reservations(request).await?.into_inner().count;
+
+        println!("Client has {} reservations:", count);
         for (i, res) in reservations.iter().enumerate() {
             println!(
                 "  Reservation #{}: ID={}, Status={}",
@@ -165,8 +162,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // Cancel a reservation
         println!("\n--- Cancelling reservation ---");
-        let request = Request::new(ReservationId {
+        let request = Request::new(CancelReservationRequest {
             id: reservation.clone().id,
+            expected_version: reservation.version,
+            client_id: client_id.clone(),
+            ..Default::default()
         });
 
         let _ = client.cancel_reservation(request).await?;
@@ -174,7 +174,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // Verify the cancellation
         println!("\n--- Listing client reservations after cancellation ---");
-        let request = Request::new(ClientId { id: client_id });
+        let request = Request::new(ListClientReservationsRequest {
+            client_id,
+            ..Default::default()
+        });
 
         let response = client.list_client_reservations(request).await?;
         let reservations = response.into_inner().reservations;
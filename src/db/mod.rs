@@ -1,5 +1,13 @@
 pub mod models;
 pub mod repository;
+#[cfg(test)]
+pub mod test_support;
 
-pub use models::{Client, Reservation, ReservationStatus, TimeSlot};
-pub use repository::{RepositoryError, ReservationRepository};
+pub use models::{
+    AvailabilityRule, AvailableSlot, BlackoutPeriod, Client, ClientCursor, ClientDeletionMode,
+    ClientSort, ClientSortField, ClientStats, CreatedSeries, NewReservation, Page, PageCursor,
+    Reservation, ReservationChange, ReservationChangeKind, ReservationEvent, ReservationFilter,
+    ReservationStatus, Resource, SlotConflicts, SlotGrid, TimeSlot, UnknownStatus, WaitlistEntry,
+    DEFAULT_RESOURCE_ID,
+};
+pub use repository::{RepositoryError, ReservationChangeListener, ReservationRepository};
@@ -1,6 +1,12 @@
-use chrono::{DateTime, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveTime, TimeZone, Timelike, Utc, Weekday,
+};
 use sqlx::postgres::PgRow;
 use sqlx::{FromRow, Row};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::str::FromStr;
 use uuid::Uuid;
 
 /// Represents a client in the system
@@ -9,6 +15,12 @@ pub struct Client {
     pub id: Uuid,
     pub name: String,
     pub email: String,
+    /// Phone number in international format, e.g. `+15551234567`
+    pub phone: Option<String>,
+    /// Most active reservations this client may hold, `None` uses the service default
+    pub reservation_quota: Option<i32>,
+    /// When the client was archived, archived clients can't make new reservations
+    pub archived_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -18,33 +30,212 @@ impl FromRow<'_, PgRow> for Client {
             id: row.try_get("id")?,
             name: row.try_get("name")?,
             email: row.try_get("email")?,
+            phone: row.try_get("phone")?,
+            reservation_quota: row.try_get("reservation_quota")?,
+            archived_at: row.try_get("archived_at")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+/// Column that client listings are ordered by, with the client ID breaking ties
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientSortField {
+    #[default]
+    CreatedAt,
+    Name,
+}
+
+/// Order of a client listing, oldest first by default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClientSort {
+    pub field: ClientSortField,
+    pub descending: bool,
+}
+
+/// Reservation counts of a client for reporting
+#[derive(Debug, Clone)]
+pub struct ClientStats {
+    /// Every reservation the client made, whatever its status
+    pub total: i64,
+    /// Confirmed reservations that haven't started yet
+    pub upcoming: i64,
+    pub cancelled: i64,
+    pub completed: i64,
+    pub no_shows: i64,
+}
+
+impl FromRow<'_, PgRow> for ClientStats {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(ClientStats {
+            total: row.try_get("total")?,
+            upcoming: row.try_get("upcoming")?,
+            cancelled: row.try_get("cancelled")?,
+            completed: row.try_get("completed")?,
+            no_shows: row.try_get("no_shows")?,
+        })
+    }
+}
+
+/// What deleting a client does with their reservations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientDeletionMode {
+    /// Refuse while the client has upcoming confirmed reservations or live holds. A client
+    /// with past reservations is anonymized rather than deleted so that history is kept.
+    #[default]
+    Refuse,
+    /// Cancel the client's upcoming reservations, then anonymize or delete the client as
+    /// `Refuse` does, keeping every reservation they made
+    Cascade,
+}
+
+/// Resource that reservations made without choosing one are booked against
+pub const DEFAULT_RESOURCE_ID: Uuid = Uuid::nil();
+
+/// Represents a bookable resource, such as a court or a room
+#[derive(Debug, Clone)]
+pub struct Resource {
+    pub id: Uuid,
+    pub name: String,
+    /// Number of reservations that may overlap on this resource at the same time
+    pub capacity: i32,
+    /// Deactivated resources keep their reservations but can't be booked
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for Resource {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Resource {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            capacity: row.try_get("capacity")?,
+            active: row.try_get("active")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+/// A client waiting for a slot on a resource to free up
+#[derive(Debug, Clone)]
+pub struct WaitlistEntry {
+    pub id: Uuid,
+    pub client_id: Uuid,
+    pub resource_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for WaitlistEntry {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(WaitlistEntry {
+            id: row.try_get("id")?,
+            client_id: row.try_get("client_id")?,
+            resource_id: row.try_get("resource_id")?,
+            start_time: row.try_get("start_time")?,
+            end_time: row.try_get("end_time")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+/// Period during which a resource, or every resource, can't be booked
+#[derive(Debug, Clone)]
+pub struct BlackoutPeriod {
+    pub id: Uuid,
+    /// Resource that is blocked, `None` when the blackout applies to every resource
+    pub resource_id: Option<Uuid>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BlackoutPeriod {
+    /// Returns true if this blackout prevents booking `resource_id` for `[start, end)`
+    pub fn blocks(&self, resource_id: Uuid, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.resource_id.is_none_or(|id| id == resource_id)
+            && self.start_time < end
+            && self.end_time > start
+    }
+}
+
+impl FromRow<'_, PgRow> for BlackoutPeriod {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(BlackoutPeriod {
+            id: row.try_get("id")?,
+            resource_id: row.try_get("resource_id")?,
+            start_time: row.try_get("start_time")?,
+            end_time: row.try_get("end_time")?,
+            reason: row.try_get("reason")?,
             created_at: row.try_get("created_at")?,
         })
     }
 }
 
 /// Status of a reservation
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// A reservation is either booked directly as `Confirmed` or starts as a `Pending` hold
+/// that is confirmed later or lapses to `Expired`. Once its time has passed a confirmed
+/// reservation ends up `Completed` or `NoShow`, and it can be `Cancelled` before that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReservationStatus {
     Confirmed,
     Cancelled,
+    /// Tentative hold that blocks its slot until `expires_at`
+    Pending,
+    /// Hold that lapsed without being confirmed
+    Expired,
+    /// The appointment took place
+    Completed,
+    /// The client didn't turn up for the appointment
+    NoShow,
+}
+
+impl ReservationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Confirmed => "confirmed",
+            ReservationStatus::Cancelled => "cancelled",
+            ReservationStatus::Pending => "pending",
+            ReservationStatus::Expired => "expired",
+            ReservationStatus::Completed => "completed",
+            ReservationStatus::NoShow => "no_show",
+        }
+    }
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
-impl From<String> for ReservationStatus {
-    fn from(s: String) -> Self {
+/// Error returned when a string doesn't name a known reservation status
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown reservation status: {0}")]
+pub struct UnknownStatus(pub String);
+
+impl FromStr for ReservationStatus {
+    type Err = UnknownStatus;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "cancelled" => ReservationStatus::Cancelled,
-            _ => ReservationStatus::Confirmed,
+            "confirmed" => Ok(ReservationStatus::Confirmed),
+            "cancelled" => Ok(ReservationStatus::Cancelled),
+            "pending" => Ok(ReservationStatus::Pending),
+            "expired" => Ok(ReservationStatus::Expired),
+            "completed" => Ok(ReservationStatus::Completed),
+            "no_show" => Ok(ReservationStatus::NoShow),
+            _ => Err(UnknownStatus(s.to_string())),
         }
     }
 }
 
 impl From<ReservationStatus> for String {
     fn from(status: ReservationStatus) -> Self {
-        match status {
-            ReservationStatus::Confirmed => "confirmed".to_string(),
-            ReservationStatus::Cancelled => "cancelled".to_string(),
-        }
+        status.as_str().to_string()
     }
 }
 
@@ -52,33 +243,483 @@ impl From<ReservationStatus> for String {
 #[derive(Debug, Clone)]
 pub struct Reservation {
     pub id: Uuid,
+    /// Short code such as `R-7QF4K2` given to the customer
+    pub reference: String,
     pub client_id: Uuid,
+    pub resource_id: Uuid,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub status: ReservationStatus,
     pub notes: Option<String>,
+    pub series_id: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub cancellation_reason: Option<String>,
+    /// When the client arrived, `None` until they check in
+    pub checked_in_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// When the reservation was last modified, equal to `created_at` until then
+    pub updated_at: DateTime<Utc>,
+    /// Starts at 1 and goes up by one with every update of the reservation
+    pub version: i32,
+    /// Clients attending besides the owner, only loaded when reservations are created, looked
+    /// up or listed and empty otherwise
+    pub attendee_client_ids: Vec<Uuid>,
 }
 
 impl FromRow<'_, PgRow> for Reservation {
     fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
         let status: String = row.try_get("status")?;
+        let status = status
+            .parse::<ReservationStatus>()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
 
         Ok(Reservation {
             id: row.try_get("id")?,
+            reference: row.try_get("reference")?,
             client_id: row.try_get("client_id")?,
+            resource_id: row.try_get("resource_id")?,
             start_time: row.try_get("start_time")?,
             end_time: row.try_get("end_time")?,
-            status: ReservationStatus::from(status),
+            status,
             notes: row.try_get("notes")?,
+            series_id: row.try_get("series_id")?,
+            expires_at: row.try_get("expires_at")?,
+            cancellation_reason: row.try_get("cancellation_reason")?,
+            checked_in_at: row.try_get("checked_in_at")?,
             created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+            version: row.try_get("version")?,
+            // Loaded separately by the queries that select them
+            attendee_client_ids: Vec::new(),
         })
     }
 }
 
+/// Entry in the audit trail of a reservation, recorded whenever its status changes
+#[derive(Debug, Clone)]
+pub struct ReservationEvent {
+    pub id: Uuid,
+    pub reservation_id: Uuid,
+    /// Status before the change, `None` for the event recording the creation
+    pub old_status: Option<ReservationStatus>,
+    pub new_status: ReservationStatus,
+    /// Who made the change, `None` when the caller didn't say
+    pub actor: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for ReservationEvent {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let old_status: Option<String> = row.try_get("old_status")?;
+        let old_status = old_status
+            .map(|status| status.parse::<ReservationStatus>())
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let new_status: String = row.try_get("new_status")?;
+        let new_status = new_status
+            .parse::<ReservationStatus>()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        Ok(ReservationEvent {
+            id: row.try_get("id")?,
+            reservation_id: row.try_get("reservation_id")?,
+            old_status,
+            new_status,
+            actor: row.try_get("actor")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+/// What happened to a reservation in a change feed event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservationChangeKind {
+    Created,
+    Cancelled,
+    /// Any other change, e.g. a reschedule, new notes or another status transition
+    Updated,
+}
+
+impl ReservationChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservationChangeKind::Created => "created",
+            ReservationChangeKind::Cancelled => "cancelled",
+            ReservationChangeKind::Updated => "updated",
+        }
+    }
+}
+
+/// Change feed event carrying the reservation as it was read after the change
+#[derive(Debug, Clone)]
+pub struct ReservationChange {
+    pub kind: ReservationChangeKind,
+    pub reservation: Reservation,
+}
+
+/// Fields needed to create a new reservation
+#[derive(Debug, Clone)]
+pub struct NewReservation {
+    pub client_id: Uuid,
+    pub resource_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub notes: Option<String>,
+    /// Key supplied by the caller so that retried creates return the original reservation
+    pub idempotency_key: Option<String>,
+    /// Create a pending hold that lapses at this time instead of a confirmed reservation
+    pub hold_expires_at: Option<DateTime<Utc>>,
+    /// Other clients sharing the reservation, without the owner or duplicates
+    pub attendee_client_ids: Vec<Uuid>,
+}
+
+/// What stands in the way of booking a slot, nothing when it can be booked
+#[derive(Debug, Clone, Default)]
+pub struct SlotConflicts {
+    /// Reservations taking up all of the resource's capacity in the slot, empty while it has
+    /// room
+    pub reservation_ids: Vec<Uuid>,
+    /// Why the slot can't be booked whatever the reservations, e.g. an overlapping blackout
+    pub unavailable_reason: Option<String>,
+}
+
+impl SlotConflicts {
+    pub fn is_empty(&self) -> bool {
+        self.reservation_ids.is_empty() && self.unavailable_reason.is_none()
+    }
+}
+
 /// Represents a time slot
 #[derive(Debug, Clone)]
 pub struct TimeSlot {
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
 }
+
+impl TimeSlot {
+    /// Returns true if this slot shares any time with the half-open interval `[start, end)`
+    ///
+    /// Intervals that only touch, one ending exactly when the other begins, don't overlap.
+    /// This matches the `&&` operator on the default `[)` ranges used by the database.
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.start_time < end && self.end_time > start
+    }
+}
+
+/// Opening window starting on a day of the week, on the local clock of the configured
+/// time zone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvailabilityRule {
+    pub day_of_week: Weekday,
+    pub open_time: NaiveTime,
+    /// Closing time, at or before `open_time` when the window runs past midnight
+    pub close_time: NaiveTime,
+}
+
+impl AvailabilityRule {
+    /// Returns true if the whole of `[start, end)` falls within one window of this rule,
+    /// reading the local clock in `tz`
+    ///
+    /// A window running past midnight belongs to the day it opens, so it also covers slots
+    /// early on the following day.
+    pub fn contains<Tz: TimeZone>(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        tz: &Tz,
+    ) -> bool {
+        let local_start = start.with_timezone(tz).naive_local();
+        let local_end = end.with_timezone(tz).naive_local();
+        let start_date = local_start.date();
+
+        [start_date.pred_opt(), Some(start_date)]
+            .into_iter()
+            .flatten()
+            .filter(|day| day.weekday() == self.day_of_week)
+            .any(|day| {
+                let opens_at = day.and_time(self.open_time);
+                let mut closes_at = day.and_time(self.close_time);
+                if self.close_time <= self.open_time {
+                    closes_at += Duration::days(1);
+                }
+
+                local_start >= opens_at && local_end <= closes_at
+            })
+    }
+}
+
+impl FromRow<'_, PgRow> for AvailabilityRule {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let day_of_week: i16 = row.try_get("day_of_week")?;
+        let day_of_week = u8::try_from(day_of_week - 1)
+            .ok()
+            .and_then(|day| Weekday::try_from(day).ok())
+            .ok_or_else(|| {
+                sqlx::Error::Decode(format!("Invalid day of week: {}", day_of_week).into())
+            })?;
+
+        Ok(AvailabilityRule {
+            day_of_week,
+            open_time: row.try_get("open_time")?,
+            close_time: row.try_get("close_time")?,
+        })
+    }
+}
+
+/// How the candidate slots of an availability search are laid out
+#[derive(Debug, Clone)]
+pub struct SlotGrid<Tz: TimeZone> {
+    /// Length of each slot
+    pub duration: Duration,
+    /// Time between the starts of consecutive slots
+    pub step: Duration,
+    /// Snap the first slot to the next start this long after a multiple of `step` since
+    /// local midnight, `None` starts right at the beginning of the range
+    pub alignment_offset: Option<Duration>,
+    /// Time zone whose local clock slots are laid out on
+    pub time_zone: Tz,
+}
+
+impl<Tz: TimeZone> SlotGrid<Tz> {
+    /// Generate the slots starting at or after `start`, in chronological order, for as long
+    /// as they end by `end`
+    ///
+    /// Slots are generated lazily, so a caller that stops early never lays out the rest of
+    /// the range. Local start times skipped by a spring-forward transition produce no slot,
+    /// and those repeated by a fall-back transition produce one for each instant they occur at.
+    pub fn slots(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl Iterator<Item = TimeSlot> + '_ {
+        let mut local = start.with_timezone(&self.time_zone).naive_local();
+
+        if let Some(offset) = self.alignment_offset {
+            let step = self.step.num_seconds();
+            let since_midnight = local.num_seconds_from_midnight() as i64;
+            let misalignment = (since_midnight - offset.num_seconds()).rem_euclid(step);
+            if misalignment != 0 || local.nanosecond() != 0 {
+                local = local.with_nanosecond(0).unwrap_or(local)
+                    + Duration::seconds(step - misalignment);
+            }
+        }
+
+        // Around a fall-back transition the later instants of repeated times come after the
+        // earlier instants of the times following them, so start times wait here until no
+        // later local time can produce an earlier one
+        let mut pending: BinaryHeap<Reverse<DateTime<Utc>>> = BinaryHeap::new();
+        let mut past_end = false;
+
+        std::iter::from_fn(move || loop {
+            if past_end {
+                return pending
+                    .pop()
+                    .map(|Reverse(start_time)| self.slot_at(start_time));
+            }
+
+            let (earliest, latest) = match self.time_zone.from_local_datetime(&local) {
+                LocalResult::Single(instant) => (Some(instant), None),
+                LocalResult::Ambiguous(earliest, latest) => (Some(earliest), Some(latest)),
+                LocalResult::None => (None, None),
+            };
+            let earliest = earliest.map(|instant| instant.with_timezone(&Utc));
+            let latest = latest.map(|instant| instant.with_timezone(&Utc));
+
+            // Nothing from this local time on starts before its earliest instant
+            if let (Some(&Reverse(next)), Some(earliest)) = (pending.peek(), earliest) {
+                if next <= earliest {
+                    pending.pop();
+                    return Some(self.slot_at(next));
+                }
+            }
+
+            for start_time in [earliest, latest].into_iter().flatten() {
+                if start_time + self.duration > end {
+                    past_end = true;
+                    break;
+                }
+                if start_time >= start {
+                    pending.push(Reverse(start_time));
+                }
+            }
+
+            if !past_end {
+                local += self.step;
+            }
+        })
+    }
+
+    fn slot_at(&self, start_time: DateTime<Utc>) -> TimeSlot {
+        TimeSlot {
+            start_time,
+            end_time: start_time + self.duration,
+        }
+    }
+}
+
+/// A free slot together with how many more reservations it can take
+#[derive(Debug, Clone)]
+pub struct AvailableSlot {
+    pub slot: TimeSlot,
+    pub remaining_capacity: i32,
+}
+
+/// Optional criteria for narrowing down reservation listings
+#[derive(Debug, Clone, Default)]
+pub struct ReservationFilter {
+    pub client_id: Option<Uuid>,
+    /// Also match reservations that `client_id` attends without owning them
+    pub include_attended: bool,
+    pub resource_id: Option<Uuid>,
+    pub status: Option<ReservationStatus>,
+    /// Only match reservations ending after this time, unbounded when `None`
+    pub start_time: Option<DateTime<Utc>>,
+    /// Only match reservations starting before this time, unbounded when `None`
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+/// Position of the last row returned by a keyset-paginated query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub timestamp: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+/// Position of the last client returned by a client listing, holding the value the listing
+/// is sorted by so the next page doesn't depend on the client still being as it was
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientCursor {
+    CreatedAt { created_at: DateTime<Utc>, id: Uuid },
+    Name { name: String, id: Uuid },
+}
+
+/// Outcome of creating the occurrences of a recurring reservation
+#[derive(Debug, Clone)]
+pub struct CreatedSeries {
+    pub reservations: Vec<Reservation>,
+    /// Occurrences left out because their slot was taken, only when skipping conflicts
+    pub skipped: Vec<TimeSlot>,
+}
+
+/// A single page of results along with the cursor to fetch the next one
+#[derive(Debug, Clone)]
+pub struct Page<T, C = PageCursor> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<C>,
+}
+
+impl<T, C> Page<T, C> {
+    /// Build a page from up to `limit + 1` rows, using the extra row to detect whether more remain
+    pub fn from_rows(mut rows: Vec<T>, limit: i64, cursor: impl Fn(&T) -> C) -> Self {
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+
+        let next_cursor = if has_more {
+            rows.last().map(cursor)
+        } else {
+            None
+        };
+
+        Page {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2030, 1, 7, hour, minute, 0).unwrap()
+    }
+
+    fn slot(start_hour: u32, end_hour: u32) -> TimeSlot {
+        TimeSlot {
+            start_time: at(start_hour, 0),
+            end_time: at(end_hour, 0),
+        }
+    }
+
+    #[test]
+    fn adjacent_intervals_do_not_overlap() {
+        // Ending exactly when the other begins, and beginning exactly when it ends
+        assert!(!slot(10, 11).overlaps(at(11, 0), at(12, 0)));
+        assert!(!slot(11, 12).overlaps(at(10, 0), at(11, 0)));
+    }
+
+    #[test]
+    fn nested_intervals_overlap() {
+        assert!(slot(10, 13).overlaps(at(11, 0), at(12, 0)));
+        assert!(slot(11, 12).overlaps(at(10, 0), at(13, 0)));
+    }
+
+    #[test]
+    fn identical_intervals_overlap() {
+        assert!(slot(10, 11).overlaps(at(10, 0), at(11, 0)));
+    }
+
+    #[test]
+    fn partially_overlapping_intervals_overlap() {
+        assert!(slot(10, 12).overlaps(at(11, 0), at(13, 0)));
+        assert!(slot(11, 13).overlaps(at(10, 0), at(12, 0)));
+    }
+
+    fn grid<Tz: TimeZone>(minutes: i64, time_zone: Tz) -> SlotGrid<Tz> {
+        SlotGrid {
+            duration: Duration::minutes(minutes),
+            step: Duration::minutes(minutes),
+            alignment_offset: None,
+            time_zone,
+        }
+    }
+
+    #[test]
+    fn slots_stop_at_the_end_of_the_range() {
+        let starts: Vec<_> = grid(30, Utc)
+            .slots(at(10, 0), at(11, 45))
+            .map(|slot| slot.start_time)
+            .collect();
+        assert_eq!(starts, vec![at(10, 0), at(10, 30), at(11, 0)]);
+    }
+
+    #[test]
+    fn slots_are_only_laid_out_as_far_as_they_are_taken() {
+        // A range of thousands of years still yields its first slots right away
+        let slots: Vec<_> = grid(1, Utc)
+            .slots(at(10, 0), DateTime::<Utc>::MAX_UTC)
+            .take(2)
+            .collect();
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[1].start_time, at(10, 1));
+    }
+
+    #[test]
+    fn slots_repeated_by_a_fall_back_come_out_in_order() {
+        // Berlin's clocks go back from 03:00 to 02:00 local time at 01:00 UTC
+        let start = Utc.with_ymd_and_hms(2030, 10, 27, 0, 0, 0).unwrap();
+        let starts: Vec<_> = grid(30, Berlin)
+            .slots(start, start + Duration::hours(3))
+            .map(|slot| slot.start_time)
+            .collect();
+
+        let expected: Vec<_> = (0..6).map(|i| start + Duration::minutes(30 * i)).collect();
+        assert_eq!(starts, expected);
+    }
+
+    #[test]
+    fn slots_skipped_by_a_spring_forward_are_left_out() {
+        // Berlin's clocks go forward from 02:00 to 03:00 local time at 01:00 UTC, so the
+        // 02:00 and 02:30 local starts never happen and 03:00 follows 01:30
+        let start = Utc.with_ymd_and_hms(2030, 3, 31, 0, 0, 0).unwrap();
+        let starts: Vec<_> = grid(30, Berlin)
+            .slots(start, start + Duration::hours(3))
+            .map(|slot| slot.start_time)
+            .collect();
+
+        let expected: Vec<_> = (0..6).map(|i| start + Duration::minutes(30 * i)).collect();
+        assert_eq!(starts, expected);
+    }
+}
@@ -1,10 +1,52 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
-use sqlx::{PgPool, Postgres, Transaction};
+use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+use sqlx::postgres::{PgListener, PgRow};
+use sqlx::{Connection, FromRow, PgPool, Postgres, Row, Transaction};
 use thiserror::Error;
+use tokio_stream::{Stream, StreamExt};
 use uuid::Uuid;
 
-use super::models::{Client, Reservation, TimeSlot};
+use super::models::{
+    AvailabilityRule, AvailableSlot, BlackoutPeriod, Client, ClientCursor, ClientDeletionMode,
+    ClientSort, ClientSortField, ClientStats, CreatedSeries, NewReservation, Page, PageCursor,
+    Reservation, ReservationChange, ReservationChangeKind, ReservationEvent, ReservationFilter,
+    ReservationStatus, Resource, SlotConflicts, SlotGrid, TimeSlot, WaitlistEntry,
+};
+
+/// How long an idempotency key keeps returning the reservation it created by default
+const DEFAULT_IDEMPOTENCY_KEY_TTL_HOURS: i32 = 24;
+
+/// How long before its start a reservation can be checked in
+const CHECK_IN_EARLY_MINUTES: i64 = 30;
+
+/// Channel the database announces committed reservation changes on
+const RESERVATION_CHANGES_CHANNEL: &str = "reservation_changes";
+
+/// Actor recorded in the audit trail for reservations created by promoting a waitlist entry
+const WAITLIST_ACTOR: &str = "waitlist";
+
+/// Select list of reservations along with their attendees, for rows read as `WithAttendees`
+///
+/// A macro rather than a `const` so that `concat!` can splice it into the query literals.
+macro_rules! reservation_with_attendees_columns {
+    () => {
+        "reservations.*, ARRAY(SELECT a.client_id FROM reservation_attendees a
+            WHERE a.reservation_id = reservations.id ORDER BY a.client_id) AS attendee_client_ids"
+    };
+}
+
+/// A reservation row selected with `reservation_with_attendees_columns!`, which unlike a plain
+/// reservation row carries the attendees
+struct WithAttendees(Reservation);
+
+impl FromRow<'_, PgRow> for WithAttendees {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(WithAttendees(Reservation {
+            attendee_client_ids: row.try_get("attendee_client_ids")?,
+            ..Reservation::from_row(row)?
+        }))
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum RepositoryError {
@@ -19,232 +61,3866 @@ pub enum RepositoryError {
 
     #[error("Client not found with ID: {0}")]
     ClientNotFound(Uuid),
+
+    #[error("Client with ID {0} is archived")]
+    ClientArchived(Uuid),
+
+    #[error("Client not found with email: {0}")]
+    ClientEmailNotFound(String),
+
+    #[error("Client with ID {id} has {count} upcoming reservations")]
+    ClientHasUpcomingReservations { id: Uuid, count: i64 },
+
+    #[error("Resource not found with ID: {0}")]
+    ResourceNotFound(Uuid),
+
+    #[error("Reservation not found with reference: {0}")]
+    ReservationReferenceNotFound(String),
+
+    #[error("Resource with ID {0} is deactivated")]
+    ResourceInactive(Uuid),
+
+    #[error("Slot falls within a blackout: {reason}")]
+    BlackedOut { id: Uuid, reason: String },
+
+    #[error("Blackout not found with ID: {0}")]
+    BlackoutNotFound(Uuid),
+
+    #[error("Waitlist entry not found with ID: {0}")]
+    WaitlistEntryNotFound(Uuid),
+
+    #[error("Reservation with ID {0} is cancelled")]
+    ReservationCancelled(Uuid),
+
+    #[error("Hold on reservation with ID {0} has expired")]
+    HoldExpired(Uuid),
+
+    #[error("Reservation with ID {id} is {status}")]
+    InvalidStatus { id: Uuid, status: ReservationStatus },
+
+    #[error("Reservation with ID {id} does not belong to client {client_id}")]
+    ReservationNotOwned { id: Uuid, client_id: Uuid },
+
+    #[error("Reservation with ID {0} was modified since it was read")]
+    VersionConflict(Uuid),
+
+    #[error("Reservation with ID {0} has not ended yet")]
+    ReservationNotEnded(Uuid),
+
+    #[error("Reservation with ID {0} cannot be checked in at this time")]
+    OutsideCheckInWindow(Uuid),
+
+    #[error("Client {client_id} already has {count} active reservations, the limit is {quota}")]
+    QuotaExceeded {
+        client_id: Uuid,
+        count: i64,
+        quota: i64,
+    },
+
+    #[error("Reservation with ID {id} already ends at {end_time}, extending must move it later")]
+    EndNotExtended { id: Uuid, end_time: DateTime<Utc> },
+
+    #[error("A client with email {0} already exists")]
+    DuplicateEmail(String),
+
+    #[error("Reservation at index {index} failed: {source}")]
+    BatchItemFailed {
+        index: usize,
+        source: Box<RepositoryError>,
+    },
+
+    #[error("Occurrence {occurrence} starting at {start_time} overlaps a reservation or blackout")]
+    SeriesConflict {
+        occurrence: usize,
+        start_time: DateTime<Utc>,
+    },
+}
+
+/// Returns true if the error was raised by a unique constraint on client emails
+fn is_duplicate_email_violation(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => matches!(
+            db_err.constraint(),
+            Some("clients_email_key" | "clients_email_normalized_key")
+        ),
+        _ => false,
+    }
+}
+
+/// Escape the wildcards and the escape character itself so `input` matches literally in LIKE
+fn escape_like_pattern(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Returns true if the error was raised by the per-client idempotency key index
+fn is_idempotency_key_violation(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => {
+            db_err.constraint() == Some("idx_reservations_idempotency_key")
+        }
+        _ => false,
+    }
 }
 
 pub struct ReservationRepository {
     pool: PgPool,
+    idempotency_key_ttl_hours: i32,
+    reservation_quota: Option<i64>,
+    blocking_statuses: Vec<ReservationStatus>,
 }
 
 impl ReservationRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            idempotency_key_ttl_hours: DEFAULT_IDEMPOTENCY_KEY_TTL_HOURS,
+            reservation_quota: None,
+            blocking_statuses: vec![ReservationStatus::Confirmed, ReservationStatus::Pending],
+        }
+    }
+
+    /// Set which statuses take up a slot for availability and capacity checks
+    ///
+    /// Defaults to confirmed and pending. A pending hold only blocks until it expires.
+    pub fn with_blocking_statuses(mut self, statuses: Vec<ReservationStatus>) -> Self {
+        self.blocking_statuses = statuses;
+        self
+    }
+
+    /// The blocking statuses in the form bound to `status = ANY(..)` in queries
+    fn blocking_status_names(&self) -> Vec<String> {
+        self.blocking_statuses
+            .iter()
+            .map(|status| String::from(*status))
+            .collect()
+    }
+
+    /// Set how many active reservations a client may hold unless they have their own quota,
+    /// `None` means unlimited
+    pub fn with_reservation_quota(mut self, quota: Option<i64>) -> Self {
+        self.reservation_quota = quota;
+        self
+    }
+
+    /// Set how many hours an idempotency key keeps returning the reservation it created
+    pub fn with_idempotency_key_ttl_hours(mut self, hours: i32) -> Self {
+        self.idempotency_key_ttl_hours = hours;
+        self
     }
 
-    pub async fn create_client(&self, name: &str, email: &str) -> Result<Client, RepositoryError> {
+    /// Create a client, storing the email trimmed and lowercased and the name and phone as given
+    pub async fn create_client(
+        &self,
+        name: &str,
+        email: &str,
+        phone: Option<&str>,
+    ) -> Result<Client, RepositoryError> {
+        let email = email.trim().to_lowercase();
+
         let client = sqlx::query_as::<_, Client>(
-            "INSERT INTO clients (name, email) VALUES ($1, $2) RETURNING *",
+            "INSERT INTO clients (name, email, phone) VALUES ($1, $2, $3) RETURNING *",
         )
         .bind(name)
-        .bind(email)
+        .bind(&email)
+        .bind(phone)
         .fetch_one(&self.pool)
-        .await?;
+        .await
+        .map_err(|err| {
+            if is_duplicate_email_violation(&err) {
+                RepositoryError::DuplicateEmail(email.clone())
+            } else {
+                RepositoryError::DatabaseError(err)
+            }
+        })?;
 
         Ok(client)
     }
 
-    pub async fn list_clients(&self) -> Result<Vec<Client>, RepositoryError> {
-        let clients = sqlx::query_as::<_, Client>("SELECT * FROM clients")
-            .fetch_all(&self.pool)
-            .await?;
-
-        Ok(clients)
-    }
-
-    pub async fn is_slot_available(
+    /// Get the client with an email, creating them with the given name and phone if there is
+    /// none yet
+    ///
+    /// The email is normalized the same way as by `create_client`. An existing client is
+    /// returned as is, even when the name or phone differ. Concurrent calls for the same
+    /// email all get the same client.
+    pub async fn get_or_create_client(
         &self,
-        start_time: DateTime<Utc>,
-        end_time: DateTime<Utc>,
-    ) -> Result<bool, RepositoryError> {
-        let count: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM reservations 
-             WHERE status = 'confirmed' 
-             AND tstzrange($1, $2) && tstzrange(start_time, end_time)",
+        name: &str,
+        email: &str,
+        phone: Option<&str>,
+    ) -> Result<Client, RepositoryError> {
+        let email = email.trim().to_lowercase();
+
+        let created = sqlx::query_as::<_, Client>(
+            "INSERT INTO clients (name, email, phone) VALUES ($1, $2, $3)
+             ON CONFLICT DO NOTHING
+             RETURNING *",
         )
-        .bind(start_time)
-        .bind(end_time)
-        .fetch_one(&self.pool)
+        .bind(name)
+        .bind(&email)
+        .bind(phone)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(count.0 == 0)
+        match created {
+            Some(client) => Ok(client),
+            None => self.find_client_by_email(&email).await,
+        }
     }
 
-    pub async fn find_available_slots(
+    /// Change the name, email and phone of a client, `None` keeps the current value
+    ///
+    /// The email is normalized the same way as by `create_client`. `Some(None)` clears the
+    /// phone.
+    pub async fn update_client(
         &self,
-        start_date: DateTime<Utc>,
-        end_date: DateTime<Utc>,
-    ) -> Result<Vec<TimeSlot>, RepositoryError> {
-        let existing_reservations = sqlx::query_as::<_, Reservation>(
-            "SELECT * FROM reservations 
-             WHERE status = 'confirmed' 
-             AND tstzrange(start_time, end_time) && tstzrange($1, $2)
-             ORDER BY start_time",
+        id: Uuid,
+        name: Option<&str>,
+        email: Option<&str>,
+        phone: Option<Option<&str>>,
+    ) -> Result<Client, RepositoryError> {
+        let email = email.map(|email| email.trim().to_lowercase());
+
+        let client = sqlx::query_as::<_, Client>(
+            "UPDATE clients SET name = COALESCE($2, name), email = COALESCE($3, email),
+                 phone = CASE WHEN $4 THEN $5 ELSE phone END
+             WHERE id = $1
+             RETURNING *",
         )
-        .bind(start_date)
-        .bind(end_date)
-        .fetch_all(&self.pool)
-        .await?;
+        .bind(id)
+        .bind(name)
+        .bind(email.as_deref())
+        .bind(phone.is_some())
+        .bind(phone.flatten())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| match &email {
+            Some(email) if is_duplicate_email_violation(&err) => {
+                RepositoryError::DuplicateEmail(email.clone())
+            }
+            _ => RepositoryError::DatabaseError(err),
+        })?
+        .ok_or(RepositoryError::ClientNotFound(id))?;
 
-        let mut available_slots = Vec::new();
-        let mut current_time = start_date;
+        Ok(client)
+    }
 
-        while current_time < end_date {
-            let slot_end = current_time + chrono::Duration::hours(1);
+    /// Get a client by ID
+    pub async fn get_client(&self, id: Uuid) -> Result<Client, RepositoryError> {
+        let client = sqlx::query_as::<_, Client>("SELECT * FROM clients WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(RepositoryError::ClientNotFound(id))?;
 
-            // Check if this slot overlaps with any existing reservation
-            let is_available = !existing_reservations.iter().any(|res| {
-                (current_time >= res.start_time && current_time < res.end_time)
-                    || (slot_end > res.start_time && slot_end <= res.end_time)
-                    || (current_time <= res.start_time && slot_end >= res.end_time)
-            });
+        Ok(client)
+    }
 
-            if is_available {
-                available_slots.push(TimeSlot {
-                    start_time: current_time,
-                    end_time: slot_end,
-                });
-            }
+    /// Get a client by email, ignoring case and surrounding whitespace
+    ///
+    /// Looks the email up in its normalized form, which the unique index on it covers.
+    pub async fn find_client_by_email(&self, email: &str) -> Result<Client, RepositoryError> {
+        let email = email.trim().to_lowercase();
 
-            current_time = slot_end;
-        }
+        let client =
+            sqlx::query_as::<_, Client>("SELECT * FROM clients WHERE email_normalized = $1")
+                .bind(&email)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or(RepositoryError::ClientEmailNotFound(email))?;
 
-        Ok(available_slots)
+        Ok(client)
     }
 
-    pub async fn create_reservation(
+    /// Archive a client, hiding them from client listings and stopping them from booking
+    ///
+    /// Their reservations are kept and their waitlist entries are dropped. Archiving an
+    /// already archived client returns them unchanged.
+    pub async fn archive_client(&self, id: Uuid) -> Result<Client, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let client = sqlx::query_as::<_, Client>(
+            "UPDATE clients SET archived_at = COALESCE(archived_at, NOW())
+             WHERE id = $1
+             RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(RepositoryError::ClientNotFound(id))?;
+
+        sqlx::query("DELETE FROM waitlist WHERE client_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(client)
+    }
+
+    /// Restore an archived client, unarchiving a client who isn't archived is a no-op
+    pub async fn unarchive_client(&self, id: Uuid) -> Result<Client, RepositoryError> {
+        let client = sqlx::query_as::<_, Client>(
+            "UPDATE clients SET archived_at = NULL WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(RepositoryError::ClientNotFound(id))?;
+
+        Ok(client)
+    }
+
+    /// Delete a client, handling their reservations according to `mode`
+    ///
+    /// With `Refuse` the deletion fails with `ClientHasUpcomingReservations` while the client
+    /// has confirmed reservations or live holds that haven't ended. With `Cascade` those are
+    /// cancelled first under `actor`, so watchers hear about them and the freed places go to
+    /// the waitlist.
+    ///
+    /// Either way the client's waitlist entries and places on reservations that haven't ended
+    /// are then dropped, and a client with reservations left is anonymized instead of deleted
+    /// so that history, audit trail included, is kept. Everything runs in one transaction.
+    pub async fn delete_client(
         &self,
-        client_id: Uuid,
-        start_time: DateTime<Utc>,
-        end_time: DateTime<Utc>,
-        notes: Option<&str>,
-    ) -> Result<Reservation, RepositoryError> {
-        // Start a transaction to ensure atomicity
+        id: Uuid,
+        mode: ClientDeletionMode,
+        actor: Option<&str>,
+    ) -> Result<(), RepositoryError> {
         let mut tx = self.pool.begin().await?;
 
-        // Check if client exists
-        let client_exists = sqlx::query("SELECT 1 FROM clients WHERE id = $1")
-            .bind(client_id)
+        // Lock the client so no reservation can be created for them mid-delete
+        let client_exists = sqlx::query("SELECT 1 FROM clients WHERE id = $1 FOR UPDATE")
+            .bind(id)
             .fetch_optional(&mut *tx)
             .await?
             .is_some();
 
         if !client_exists {
-            return Err(RepositoryError::ClientNotFound(client_id));
+            return Err(RepositoryError::ClientNotFound(id));
         }
 
-        // Try to create the reservation
-        // The database constraint will prevent overlapping reservations
-        let result = self
-            .create_reservation_tx(&mut tx, client_id, start_time, end_time, notes)
-            .await;
+        // Drop the waitlist entries first so the client isn't promoted into their own freed places
+        sqlx::query("DELETE FROM waitlist WHERE client_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
 
-        match result {
-            Ok(reservation) => {
-                // Commit the transaction
-                tx.commit().await?;
-                Ok(reservation)
+        match mode {
+            ClientDeletionMode::Refuse => {
+                let (count,): (i64,) = sqlx::query_as(
+                    "SELECT COUNT(*) FROM reservations
+                     WHERE client_id = $1 AND end_time > NOW()
+                     AND (status = 'confirmed' OR (status = 'pending' AND expires_at > NOW()))",
+                )
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                if count > 0 {
+                    return Err(RepositoryError::ClientHasUpcomingReservations { id, count });
+                }
             }
-            Err(err) => {
-                // Rollback on error
-                let _ = tx.rollback().await;
+            ClientDeletionMode::Cascade => {
+                let upcoming = sqlx::query_as::<_, Reservation>(
+                    "SELECT * FROM reservations
+                     WHERE client_id = $1 AND status IN ('confirmed', 'pending') AND end_time > NOW()
+                     ORDER BY id
+                     FOR UPDATE",
+                )
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await?;
+
+                let ids: Vec<Uuid> = upcoming.iter().map(|res| res.id).collect();
+                sqlx::query(
+                    "UPDATE reservations
+                     SET status = 'cancelled', expires_at = NULL,
+                         cancellation_reason = 'Client deleted'
+                     WHERE id = ANY($1)",
+                )
+                .bind(&ids)
+                .execute(&mut *tx)
+                .await?;
 
-                // Check if this was a conflict error
-                if let RepositoryError::DatabaseError(sqlx::Error::Database(ref db_err)) = err {
-                    if db_err.constraint() == Some("no_overlapping_reservations") {
-                        return Err(RepositoryError::ReservationConflict);
+                for old_status in [ReservationStatus::Confirmed, ReservationStatus::Pending] {
+                    let changed: Vec<Uuid> = upcoming
+                        .iter()
+                        .filter(|res| res.status == old_status)
+                        .map(|res| res.id)
+                        .collect();
+
+                    if !changed.is_empty() {
+                        Self::record_events_tx(
+                            &mut tx,
+                            &changed,
+                            Some(old_status),
+                            ReservationStatus::Cancelled,
+                            actor,
+                        )
+                        .await?;
                     }
                 }
 
-                Err(err)
+                // Hand each freed place to whoever has been waiting for it longest
+                for freed in &upcoming {
+                    self.promote_waitlist_tx(&mut tx, freed).await?;
+                }
             }
         }
+
+        sqlx::query(
+            "DELETE FROM reservation_attendees
+             WHERE client_id = $1
+             AND reservation_id IN (SELECT id FROM reservations WHERE end_time > NOW())",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        let has_reservations =
+            sqlx::query("SELECT 1 FROM reservations WHERE client_id = $1 LIMIT 1")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .is_some();
+
+        if has_reservations {
+            // The placeholder email is unique per client and can never be delivered to
+            sqlx::query(
+                "UPDATE clients
+                 SET name = 'Deleted client', email = 'deleted-' || id || '@invalid',
+                     phone = NULL, reservation_quota = NULL
+                 WHERE id = $1",
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            sqlx::query("DELETE FROM clients WHERE id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
     }
 
-    /// Helper function to create a reservation within a transaction
-    async fn create_reservation_tx(
+    /// Get a page of clients in the order given by `sort`
+    ///
+    /// Returns at most `limit` clients positioned strictly after `after`, which must come from
+    /// a listing with the same sort. Archived clients are left out unless `include_archived`
+    /// is set.
+    pub async fn list_clients(
         &self,
-        tx: &mut Transaction<'_, Postgres>,
-        client_id: Uuid,
-        start_time: DateTime<Utc>,
-        end_time: DateTime<Utc>,
-        notes: Option<&str>,
-    ) -> Result<Reservation, RepositoryError> {
-        let reservation = sqlx::query_as::<_, Reservation>(
-            "INSERT INTO reservations (client_id, start_time, end_time, notes)
-             VALUES ($1, $2, $3, $4)
-             RETURNING *",
+        limit: i64,
+        after: Option<ClientCursor>,
+        sort: ClientSort,
+        include_archived: bool,
+    ) -> Result<Page<Client, ClientCursor>, RepositoryError> {
+        let column = match sort.field {
+            ClientSortField::CreatedAt => "created_at",
+            ClientSortField::Name => "name",
+        };
+        let (created_at, name, id) = match after {
+            Some(ClientCursor::CreatedAt { created_at, id }) => (Some(created_at), None, Some(id)),
+            Some(ClientCursor::Name { name, id }) => (None, Some(name), Some(id)),
+            None => (None, None, None),
+        };
+        // A cursor left by the other sort has no value for the column and matches nothing
+        let position = match sort.field {
+            ClientSortField::CreatedAt => "$1::timestamptz",
+            ClientSortField::Name => "$5::text",
+        };
+        let (comparison, direction) = if sort.descending {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+
+        // Fetch one extra row to find out whether there is another page
+        let query = format!(
+            "SELECT * FROM clients
+             WHERE ($2::uuid IS NULL OR ({column}, id) {comparison} ({position}, $2))
+             AND ($4 OR archived_at IS NULL)
+             ORDER BY {column} {direction}, id {direction}
+             LIMIT $3"
+        );
+        let clients = sqlx::query_as::<_, Client>(&query)
+            .bind(created_at)
+            .bind(id)
+            .bind(limit + 1)
+            .bind(include_archived)
+            .bind(name)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(Page::from_rows(clients, limit, |client| match sort.field {
+            ClientSortField::CreatedAt => ClientCursor::CreatedAt {
+                created_at: client.created_at,
+                id: client.id,
+            },
+            ClientSortField::Name => ClientCursor::Name {
+                name: client.name.clone(),
+                id: client.id,
+            },
+        }))
+    }
+
+    /// Find up to `limit` clients whose name or email contains `query`, ignoring case
+    ///
+    /// `%` and `_` in the query match themselves rather than acting as wildcards. Results
+    /// are ranked by how closely the name or email resembles the query, then by name.
+    /// Archived clients are left out.
+    pub async fn search_clients(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<Client>, RepositoryError> {
+        let pattern = format!("%{}%", escape_like_pattern(query));
+
+        let clients = sqlx::query_as::<_, Client>(
+            "SELECT * FROM clients
+             WHERE (name ILIKE $1 ESCAPE '\\' OR email ILIKE $1 ESCAPE '\\')
+             AND archived_at IS NULL
+             ORDER BY GREATEST(similarity(name, $2), similarity(email, $2)) DESC, name, id
+             LIMIT $3",
         )
-        .bind(client_id)
-        .bind(start_time)
-        .bind(end_time)
-        .bind(notes)
-        .fetch_one(&mut **tx)
+        .bind(pattern)
+        .bind(query)
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(reservation)
+        Ok(clients)
     }
 
-    /// Get a reservation by ID
-    pub async fn get_reservation(&self, id: Uuid) -> Result<Reservation, RepositoryError> {
-        let reservation =
-            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1")
-                .bind(id)
-                .fetch_optional(&self.pool)
-                .await?
-                .ok_or(RepositoryError::ReservationNotFound(id))?;
+    /// Create a resource that can hold up to `capacity` overlapping reservations
+    pub async fn create_resource(
+        &self,
+        name: &str,
+        capacity: i32,
+    ) -> Result<Resource, RepositoryError> {
+        let resource = sqlx::query_as::<_, Resource>(
+            "INSERT INTO resources (name, capacity) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(name)
+        .bind(capacity)
+        .fetch_one(&self.pool)
+        .await?;
 
-        Ok(reservation)
+        Ok(resource)
     }
 
-    /// Cancel a reservation
-    pub async fn cancel_reservation(&self, id: Uuid) -> Result<(), RepositoryError> {
-        let rows_affected = sqlx::query(
-            "UPDATE reservations SET status = 'cancelled' WHERE id = $1 AND status = 'confirmed'",
+    /// Get a resource by ID
+    pub async fn get_resource(&self, id: Uuid) -> Result<Resource, RepositoryError> {
+        let resource = sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(RepositoryError::ResourceNotFound(id))?;
+
+        Ok(resource)
+    }
+
+    /// Take a resource out of service so it can't be booked any more
+    ///
+    /// Existing reservations on the resource are kept. Deactivating an inactive resource
+    /// returns it unchanged.
+    pub async fn deactivate_resource(&self, id: Uuid) -> Result<Resource, RepositoryError> {
+        let resource = sqlx::query_as::<_, Resource>(
+            "UPDATE resources SET active = FALSE WHERE id = $1 RETURNING *",
         )
         .bind(id)
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?
-        .rows_affected();
+        .ok_or(RepositoryError::ResourceNotFound(id))?;
 
-        if rows_affected == 0 {
-            // Check if the reservation exists
-            let exists = sqlx::query("SELECT 1 FROM reservations WHERE id = $1")
-                .bind(id)
-                .fetch_optional(&self.pool)
-                .await?
-                .is_some();
+        Ok(resource)
+    }
 
-            if !exists {
-                return Err(RepositoryError::ReservationNotFound(id));
-            }
-            // If it exists but wasn't updated, it was already cancelled
-        }
+    /// Get every resource ordered by name, including deactivated ones
+    pub async fn list_resources(&self) -> Result<Vec<Resource>, RepositoryError> {
+        let resources = sqlx::query_as::<_, Resource>("SELECT * FROM resources ORDER BY name, id")
+            .fetch_all(&self.pool)
+            .await?;
 
-        Ok(())
+        Ok(resources)
     }
 
-    /// Get all reservations for a client
-    pub async fn get_client_reservations(
+    /// Replace every availability rule with `rules`, an empty list removes them all
+    pub async fn set_availability_rules(
         &self,
-        client_id: Uuid,
-    ) -> Result<Vec<Reservation>, RepositoryError> {
-        // Check if client exists
-        let client_exists = sqlx::query("SELECT 1 FROM clients WHERE id = $1")
-            .bind(client_id)
-            .fetch_optional(&self.pool)
-            .await?
-            .is_some();
+        rules: &[AvailabilityRule],
+    ) -> Result<Vec<AvailabilityRule>, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
 
-        if !client_exists {
-            return Err(RepositoryError::ClientNotFound(client_id));
-        }
+        sqlx::query("DELETE FROM availability_rules")
+            .execute(&mut *tx)
+            .await?;
+
+        let days: Vec<i16> = rules
+            .iter()
+            .map(|rule| rule.day_of_week.number_from_monday() as i16)
+            .collect();
+        let open_times: Vec<NaiveTime> = rules.iter().map(|rule| rule.open_time).collect();
+        let close_times: Vec<NaiveTime> = rules.iter().map(|rule| rule.close_time).collect();
 
-        let reservations = sqlx::query_as::<_, Reservation>(
-            "SELECT * FROM reservations WHERE client_id = $1 ORDER BY start_time",
+        sqlx::query(
+            "INSERT INTO availability_rules (day_of_week, open_time, close_time)
+             SELECT * FROM UNNEST($1::smallint[], $2::time[], $3::time[])",
+        )
+        .bind(&days)
+        .bind(&open_times)
+        .bind(&close_times)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.list_availability_rules().await
+    }
+
+    /// Get every availability rule ordered by day of the week and opening time
+    pub async fn list_availability_rules(&self) -> Result<Vec<AvailabilityRule>, RepositoryError> {
+        let rules = sqlx::query_as::<_, AvailabilityRule>(
+            "SELECT * FROM availability_rules ORDER BY day_of_week, open_time",
         )
-        .bind(client_id)
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(reservations)
+        Ok(rules)
+    }
+
+    /// Count all clients
+    pub async fn count_clients(&self) -> Result<i64, RepositoryError> {
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM clients")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count.0)
+    }
+
+    /// Returns true if the resource is bookable in the slot and has capacity left in it for
+    /// another reservation
+    pub async fn is_slot_available(
+        &self,
+        resource_id: Uuid,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<bool, RepositoryError> {
+        let conflicts = self
+            .find_slot_conflicts(resource_id, start_time, end_time)
+            .await?;
+
+        Ok(conflicts.is_empty())
+    }
+
+    /// Find what stands in the way of booking a slot on a resource
+    ///
+    /// A deactivated resource or a blackout overlapping the slot make it unavailable whatever
+    /// the reservations. Otherwise the IDs of every reservation overlapping the slot are
+    /// returned when the resource is full, and nothing when the slot is available.
+    pub async fn find_slot_conflicts(
+        &self,
+        resource_id: Uuid,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<SlotConflicts, RepositoryError> {
+        let conflicts = self
+            .find_slot_conflicts_batch(
+                resource_id,
+                &[TimeSlot {
+                    start_time,
+                    end_time,
+                }],
+            )
+            .await?;
+
+        Ok(conflicts.into_iter().next().unwrap_or_default())
+    }
+
+    /// Find the conflicts for many candidate slots on a resource in one query per kind
+    ///
+    /// Each entry of the result lines up with the slot at the same position and holds what
+    /// `find_slot_conflicts` would return for it.
+    pub async fn find_slot_conflicts_batch(
+        &self,
+        resource_id: Uuid,
+        slots: &[TimeSlot],
+    ) -> Result<Vec<SlotConflicts>, RepositoryError> {
+        let resource = self.get_resource(resource_id).await?;
+
+        if !resource.active {
+            let conflicts = SlotConflicts {
+                reservation_ids: Vec::new(),
+                unavailable_reason: Some(format!(
+                    "Resource with ID {} is deactivated",
+                    resource_id
+                )),
+            };
+            return Ok(vec![conflicts; slots.len()]);
+        }
+
+        let starts: Vec<_> = slots.iter().map(|slot| slot.start_time).collect();
+        let ends: Vec<_> = slots.iter().map(|slot| slot.end_time).collect();
+
+        // The earliest blackout overlapping each slot, as `check_capacity_tx` reports
+        let blackouts = sqlx::query_as::<_, (i64, String)>(
+            "SELECT DISTINCT ON (candidate.idx) candidate.idx, b.reason
+             FROM UNNEST($2::timestamptz[], $3::timestamptz[])
+                WITH ORDINALITY AS candidate(start_time, end_time, idx)
+             JOIN blackout_periods b ON (b.resource_id IS NULL OR b.resource_id = $1)
+                AND tstzrange(candidate.start_time, candidate.end_time)
+                    && tstzrange(b.start_time, b.end_time)
+             ORDER BY candidate.idx, b.start_time, b.id",
+        )
+        .bind(resource_id)
+        .bind(&starts)
+        .bind(&ends)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let overlapping = sqlx::query_as::<_, (i64, Uuid)>(
+            "SELECT candidate.idx, r.id
+             FROM UNNEST($2::timestamptz[], $3::timestamptz[])
+                WITH ORDINALITY AS candidate(start_time, end_time, idx)
+             JOIN reservations r ON r.resource_id = $1
+                AND r.status = ANY($4) AND (r.status <> 'pending' OR r.expires_at > NOW())
+                AND tstzrange(candidate.start_time, candidate.end_time)
+                    && tstzrange(r.start_time, r.end_time)
+             ORDER BY candidate.idx, r.start_time, r.id",
+        )
+        .bind(resource_id)
+        .bind(&starts)
+        .bind(&ends)
+        .bind(self.blocking_status_names())
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Ordinality is 1-based
+        let mut conflicts = vec![SlotConflicts::default(); slots.len()];
+        for (idx, reason) in blackouts {
+            conflicts[idx as usize - 1].unavailable_reason =
+                Some(format!("Slot falls within a blackout: {}", reason));
+        }
+        for (idx, id) in overlapping {
+            conflicts[idx as usize - 1].reservation_ids.push(id);
+        }
+
+        for slot_conflicts in &mut conflicts {
+            if (slot_conflicts.reservation_ids.len() as i64) < resource.capacity as i64 {
+                slot_conflicts.reservation_ids.clear();
+            }
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Find the free slots laid out by `grid` between `start_date` and `end_date`.
+    ///
+    /// Slots are returned in chronological order, a trailing slot that would run past
+    /// `end_date` is not returned.
+    /// A slot is free while fewer reservations on the resource overlap it than the resource's
+    /// capacity, and only slots accepted by `offered` are returned along with the number of
+    /// reservations each can still take. Generation stops once
+    /// `max_results` slots have been found. A deactivated resource has no free slots, and
+    /// slots overlapping a blackout of the resource aren't free either.
+    pub async fn find_available_slots<Tz: TimeZone>(
+        &self,
+        resource_id: Uuid,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        grid: &SlotGrid<Tz>,
+        max_results: Option<usize>,
+        offered: impl Fn(&TimeSlot) -> bool,
+    ) -> Result<Vec<AvailableSlot>, RepositoryError> {
+        let resource = self.get_resource(resource_id).await?;
+        if !resource.active {
+            return Ok(Vec::new());
+        }
+
+        let existing_reservations = sqlx::query_as::<_, Reservation>(
+            "SELECT * FROM reservations
+             WHERE resource_id = $1
+             AND status = ANY($4) AND (status <> 'pending' OR expires_at > NOW())
+             AND tstzrange(start_time, end_time) && tstzrange($2, $3)
+             ORDER BY start_time",
+        )
+        .bind(resource_id)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(self.blocking_status_names())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let blackouts = self
+            .find_blackouts(&[resource_id], start_date, end_date)
+            .await?;
+
+        let mut available_slots = Vec::new();
+
+        for slot in grid.slots(start_date, end_date) {
+            if max_results.is_some_and(|max| available_slots.len() >= max) {
+                break;
+            }
+
+            // Check if the reservations overlapping this slot leave any capacity
+            let overlapping = existing_reservations
+                .iter()
+                .filter(|res| slot.overlaps(res.start_time, res.end_time))
+                .count();
+            let remaining_capacity = resource.capacity - overlapping as i32;
+            let blacked_out = blackouts
+                .iter()
+                .any(|blackout| blackout.blocks(resource_id, slot.start_time, slot.end_time));
+
+            if remaining_capacity > 0 && !blacked_out && offered(&slot) {
+                available_slots.push(AvailableSlot {
+                    slot,
+                    remaining_capacity,
+                });
+            }
+        }
+
+        Ok(available_slots)
+    }
+
+    /// Find the longest stretches between `start_date` and `end_date` during which the
+    /// resource has room for another reservation, in chronological order
+    ///
+    /// Reservations and blackouts running past either end of the range are clipped to it, so
+    /// a range with nothing booked comes back as a single interval. A deactivated resource
+    /// has no free intervals.
+    pub async fn find_free_intervals(
+        &self,
+        resource_id: Uuid,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<TimeSlot>, RepositoryError> {
+        let resource = self.get_resource(resource_id).await?;
+        if !resource.active {
+            return Ok(Vec::new());
+        }
+
+        // Sweep over the points where occupancy changes, a blackout takes up every place, then
+        // merge the consecutive stretches that still have room
+        let intervals = sqlx::query_as::<_, (DateTime<Utc>, DateTime<Utc>)>(
+            "WITH busy AS (
+                SELECT GREATEST(start_time, $2) AS busy_start, LEAST(end_time, $3) AS busy_end,
+                    1 AS places
+                FROM reservations
+                WHERE resource_id = $1
+                AND status = ANY($4) AND (status <> 'pending' OR expires_at > NOW())
+                AND tstzrange(start_time, end_time) && tstzrange($2, $3)
+                UNION ALL
+                SELECT GREATEST(start_time, $2), LEAST(end_time, $3), $5
+                FROM blackout_periods
+                WHERE (resource_id IS NULL OR resource_id = $1)
+                AND tstzrange(start_time, end_time) && tstzrange($2, $3)
+             ),
+             changes AS (
+                SELECT busy_start AS at, places AS delta FROM busy
+                UNION ALL SELECT busy_end, -places FROM busy
+                UNION ALL SELECT $2, 0
+                UNION ALL SELECT $3, 0
+             ),
+             points AS (
+                SELECT at, SUM(delta) AS delta FROM changes GROUP BY at
+             ),
+             occupancy AS (
+                SELECT at, LEAD(at) OVER (ORDER BY at) AS next_at,
+                    SUM(delta) OVER (ORDER BY at) AS occupied
+                FROM points
+             ),
+             free AS (
+                SELECT at, next_at,
+                    CASE WHEN at = LAG(next_at) OVER (ORDER BY at) THEN 0 ELSE 1 END AS opens
+                FROM occupancy
+                WHERE next_at IS NOT NULL AND occupied < $5
+             ),
+             islands AS (
+                SELECT at, next_at, SUM(opens) OVER (ORDER BY at) AS island FROM free
+             )
+             SELECT MIN(at), MAX(next_at) FROM islands GROUP BY island ORDER BY MIN(at)",
+        )
+        .bind(resource_id)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(self.blocking_status_names())
+        .bind(resource.capacity)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|(start_time, end_time)| TimeSlot {
+            start_time,
+            end_time,
+        })
+        .collect();
+
+        Ok(intervals)
+    }
+
+    /// Create a reservation, recording `actor` as its creator in the audit trail
+    pub async fn create_reservation(
+        &self,
+        new: &NewReservation,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        // Start a transaction to ensure atomicity
+        let mut tx = self.pool.begin().await?;
+
+        // Try to create the reservation, this fails if the resource is fully booked
+        let result = self.book_tx(&mut tx, new, actor).await;
+
+        match result {
+            Ok(reservation) => {
+                // Commit the transaction
+                tx.commit().await?;
+                Ok(reservation)
+            }
+            Err(err) => {
+                // Rollback on error
+                let _ = tx.rollback().await;
+
+                if let RepositoryError::DatabaseError(ref db_err) = err {
+                    // A concurrent request with the same key won the race, return its reservation
+                    if is_idempotency_key_violation(db_err) {
+                        if let Some(key) = new.idempotency_key.as_deref() {
+                            let existing = sqlx::query_as::<_, WithAttendees>(concat!(
+                                "SELECT ",
+                                reservation_with_attendees_columns!(),
+                                " FROM reservations WHERE client_id = $1 AND idempotency_key = $2"
+                            ))
+                            .bind(new.client_id)
+                            .bind(key)
+                            .fetch_one(&self.pool)
+                            .await?
+                            .0;
+
+                            return Ok(existing);
+                        }
+                    }
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Create a batch of reservations in a single transaction
+    ///
+    /// Each entry of the result lines up with the input at the same position. Every
+    /// reservation goes through the same rules as `create_reservation` in input order, so an
+    /// earlier reservation in the batch wins over a later one and a repeated idempotency key
+    /// returns the reservation created first. When `skip_failures` is set a reservation that
+    /// fails is rolled back to its savepoint and reported while the rest are committed,
+    /// otherwise the first failure rolls back the whole batch and is returned as
+    /// `BatchItemFailed`.
+    pub async fn create_reservations(
+        &self,
+        items: &[NewReservation],
+        skip_failures: bool,
+        actor: Option<&str>,
+    ) -> Result<Vec<Result<Reservation, RepositoryError>>, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the referenced clients and then resources in a fixed order up front, the same
+        // order a single create takes them in, so concurrent batches can't deadlock
+        let mut client_ids: Vec<Uuid> = items.iter().map(|item| item.client_id).collect();
+        client_ids.sort();
+        client_ids.dedup();
+        sqlx::query("SELECT 1 FROM clients WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+            .bind(&client_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut resource_ids: Vec<Uuid> = items.iter().map(|item| item.resource_id).collect();
+        resource_ids.sort();
+        resource_ids.dedup();
+        sqlx::query("SELECT 1 FROM resources WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+            .bind(&resource_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut results = Vec::with_capacity(items.len());
+
+        for (index, item) in items.iter().enumerate() {
+            let mut savepoint = tx.begin().await?;
+
+            match self.book_tx(&mut savepoint, item, actor).await {
+                Ok(reservation) => {
+                    savepoint.commit().await?;
+                    results.push(Ok(reservation));
+                }
+                Err(err) => {
+                    savepoint.rollback().await?;
+
+                    if !skip_failures {
+                        let _ = tx.rollback().await;
+                        return Err(RepositoryError::BatchItemFailed {
+                            index,
+                            source: Box::new(err),
+                        });
+                    }
+
+                    results.push(Err(err));
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(results)
+    }
+
+    /// Create every occurrence of a recurring reservation in a single transaction
+    ///
+    /// Occurrences are booked at the given slots, in order, with everything else taken from
+    /// `new`, and all of them share a fresh series ID. If any occurrence's slot is taken or
+    /// blacked out nothing is created and the colliding occurrence is reported, unless
+    /// `skip_conflicts` is set in which case the remaining occurrences are created and the
+    /// skipped ones returned. Only the occurrences created count towards the client's quota.
+    /// The idempotency key, if any, is attached to the first occurrence created and a retry
+    /// returns the whole original series.
+    pub async fn create_recurring_reservation(
+        &self,
+        new: &NewReservation,
+        occurrences: &[TimeSlot],
+        skip_conflicts: bool,
+        actor: Option<&str>,
+    ) -> Result<CreatedSeries, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the client so concurrent creates can't both fit the quota
+        Self::lock_bookable_client_tx(&mut tx, new.client_id).await?;
+
+        if let Some(key) = new.idempotency_key.as_deref() {
+            if let Some(existing) = self
+                .find_by_idempotency_key_tx(&mut tx, new.client_id, key)
+                .await?
+            {
+                let series = match existing.series_id {
+                    Some(series_id) => sqlx::query_as::<_, WithAttendees>(concat!(
+                        "SELECT ",
+                        reservation_with_attendees_columns!(),
+                        " FROM reservations WHERE series_id = $1 ORDER BY start_time"
+                    ))
+                    .bind(series_id)
+                    .fetch_all(&mut *tx)
+                    .await?
+                    .into_iter()
+                    .map(|row| row.0)
+                    .collect(),
+                    None => vec![existing],
+                };
+
+                tx.commit().await?;
+                return Ok(CreatedSeries {
+                    reservations: series,
+                    skipped: Vec::new(),
+                });
+            }
+        }
+
+        let series_id = Uuid::new_v4();
+        let mut reservations = Vec::with_capacity(occurrences.len());
+        let mut skipped = Vec::new();
+
+        for (occurrence, slot) in occurrences.iter().enumerate() {
+            let occurrence_new = NewReservation {
+                start_time: slot.start_time,
+                end_time: slot.end_time,
+                idempotency_key: if reservations.is_empty() {
+                    new.idempotency_key.clone()
+                } else {
+                    None
+                },
+                ..new.clone()
+            };
+
+            // The capacity check fails before anything is written, so the transaction can
+            // carry on with the next occurrence
+            match self
+                .create_reservation_tx(&mut tx, &occurrence_new, Some(series_id), actor)
+                .await
+            {
+                Ok(reservation) => reservations.push(reservation),
+                Err(RepositoryError::ReservationConflict | RepositoryError::BlackedOut { .. })
+                    if skip_conflicts =>
+                {
+                    skipped.push(TimeSlot {
+                        start_time: occurrence_new.start_time,
+                        end_time: occurrence_new.end_time,
+                    });
+                }
+                Err(RepositoryError::ReservationConflict | RepositoryError::BlackedOut { .. }) => {
+                    let _ = tx.rollback().await;
+                    return Err(RepositoryError::SeriesConflict {
+                        occurrence: occurrence + 1,
+                        start_time: occurrence_new.start_time,
+                    });
+                }
+                Err(err) => {
+                    let _ = tx.rollback().await;
+                    return Err(err);
+                }
+            }
+        }
+
+        // The new occurrences are already counted, so report how many the client had before
+        if !reservations.is_empty() {
+            if let Err(err) = self.check_quota_tx(&mut tx, new.client_id, 0).await {
+                let _ = tx.rollback().await;
+                return Err(match err {
+                    RepositoryError::QuotaExceeded {
+                        client_id,
+                        count,
+                        quota,
+                    } => RepositoryError::QuotaExceeded {
+                        client_id,
+                        count: count - reservations.len() as i64,
+                        quota,
+                    },
+                    err => err,
+                });
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(CreatedSeries {
+            reservations,
+            skipped,
+        })
+    }
+
+    /// Book a reservation within a transaction, applying every rule a new booking is held to
+    ///
+    /// The client is locked and must be bookable, a live idempotency key returns the
+    /// reservation it created, and otherwise the client's quota and the resource's capacity
+    /// are checked before the reservation is inserted.
+    async fn book_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        new: &NewReservation,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        // Lock the client so concurrent creates can't both fit the quota
+        Self::lock_bookable_client_tx(tx, new.client_id).await?;
+
+        // A retry carrying a live idempotency key gets the original reservation back
+        if let Some(key) = new.idempotency_key.as_deref() {
+            if let Some(existing) = self
+                .find_by_idempotency_key_tx(tx, new.client_id, key)
+                .await?
+            {
+                return Ok(existing);
+            }
+        }
+
+        self.check_quota_tx(tx, new.client_id, 1).await?;
+
+        self.create_reservation_tx(tx, new, None, actor).await
+    }
+
+    /// Helper function to create a reservation within a transaction
+    async fn create_reservation_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        new: &NewReservation,
+        series_id: Option<Uuid>,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        self.check_capacity_tx(tx, new.resource_id, new.start_time, new.end_time, None)
+            .await?;
+
+        let status = if new.hold_expires_at.is_some() {
+            ReservationStatus::Pending
+        } else {
+            ReservationStatus::Confirmed
+        };
+
+        let reservation = sqlx::query_as::<_, Reservation>(
+            "INSERT INTO reservations
+                (client_id, resource_id, start_time, end_time, notes, idempotency_key, series_id, status, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING *",
+        )
+        .bind(new.client_id)
+        .bind(new.resource_id)
+        .bind(new.start_time)
+        .bind(new.end_time)
+        .bind(new.notes.as_deref())
+        .bind(new.idempotency_key.as_deref())
+        .bind(series_id)
+        .bind(String::from(status))
+        .bind(new.hold_expires_at)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Self::record_events_tx(tx, &[reservation.id], None, status, actor).await?;
+
+        // Attendees go in the same transaction, so an unknown one rolls back the reservation
+        let attendee_client_ids =
+            Self::add_attendees_tx(tx, reservation.id, &new.attendee_client_ids).await?;
+
+        Ok(Reservation {
+            attendee_client_ids,
+            ..reservation
+        })
+    }
+
+    /// Append an event to the audit trail of each reservation moving to `new_status`
+    ///
+    /// `old_status` is `None` when the reservations were just created.
+    async fn record_events_tx(
+        tx: &mut Transaction<'_, Postgres>,
+        reservation_ids: &[Uuid],
+        old_status: Option<ReservationStatus>,
+        new_status: ReservationStatus,
+        actor: Option<&str>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query(
+            "INSERT INTO reservation_events (reservation_id, old_status, new_status, actor)
+             SELECT UNNEST($1::uuid[]), $2, $3, $4",
+        )
+        .bind(reservation_ids)
+        .bind(old_status.map(String::from))
+        .bind(String::from(new_status))
+        .bind(actor)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lock a client who is about to book, failing with `ClientNotFound` if they don't exist
+    /// or with `ClientArchived` if they have been archived
+    async fn lock_bookable_client_tx(
+        tx: &mut Transaction<'_, Postgres>,
+        client_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        let (archived_at,): (Option<DateTime<Utc>>,) =
+            sqlx::query_as("SELECT archived_at FROM clients WHERE id = $1 FOR UPDATE")
+                .bind(client_id)
+                .fetch_optional(&mut **tx)
+                .await?
+                .ok_or(RepositoryError::ClientNotFound(client_id))?;
+
+        if archived_at.is_some() {
+            return Err(RepositoryError::ClientArchived(client_id));
+        }
+
+        Ok(())
+    }
+
+    /// Record the attendees of a reservation, failing with `ClientNotFound` naming the first
+    /// one that doesn't exist
+    async fn add_attendees_tx(
+        tx: &mut Transaction<'_, Postgres>,
+        reservation_id: Uuid,
+        attendee_ids: &[Uuid],
+    ) -> Result<Vec<Uuid>, RepositoryError> {
+        if attendee_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let existing: Vec<Uuid> =
+            sqlx::query_as::<_, (Uuid,)>("SELECT id FROM clients WHERE id = ANY($1)")
+                .bind(attendee_ids)
+                .fetch_all(&mut **tx)
+                .await?
+                .into_iter()
+                .map(|(id,)| id)
+                .collect();
+
+        if let Some(missing) = attendee_ids.iter().find(|id| !existing.contains(id)) {
+            return Err(RepositoryError::ClientNotFound(*missing));
+        }
+
+        let mut attendees: Vec<Uuid> = sqlx::query_as::<_, (Uuid,)>(
+            "INSERT INTO reservation_attendees (reservation_id, client_id)
+             SELECT $1, UNNEST($2::uuid[])
+             RETURNING client_id",
+        )
+        .bind(reservation_id)
+        .bind(attendee_ids)
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|(id,)| id)
+        .collect();
+        attendees.sort();
+
+        Ok(attendees)
+    }
+
+    /// Lock a client and fail with `QuotaExceeded` if `additional` more active reservations
+    /// would take them over their quota
+    ///
+    /// Active reservations are confirmed ones and live holds that haven't ended yet. A quota
+    /// set on the client takes precedence over the repository default.
+    async fn check_quota_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        client_id: Uuid,
+        additional: i64,
+    ) -> Result<(), RepositoryError> {
+        let (client_quota,): (Option<i32>,) =
+            sqlx::query_as("SELECT reservation_quota FROM clients WHERE id = $1 FOR UPDATE")
+                .bind(client_id)
+                .fetch_optional(&mut **tx)
+                .await?
+                .ok_or(RepositoryError::ClientNotFound(client_id))?;
+
+        let Some(quota) = client_quota.map(i64::from).or(self.reservation_quota) else {
+            return Ok(());
+        };
+
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM reservations
+             WHERE client_id = $1
+             AND (status = 'confirmed' OR (status = 'pending' AND expires_at > NOW()))
+             AND end_time > NOW()",
+        )
+        .bind(client_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        if count + additional > quota {
+            return Err(RepositoryError::QuotaExceeded {
+                client_id,
+                count,
+                quota,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Lock a resource and fail with `ReservationConflict` if it is fully booked for a slot,
+    /// with `ResourceInactive` if it has been deactivated or with `BlackedOut` if the slot
+    /// overlaps a blackout of the resource
+    ///
+    /// The resource is full once as many reservations with a blocking status overlap the slot
+    /// as its capacity, not counting `exclude`. The row lock is held until the transaction
+    /// ends so concurrent bookings of the same resource can't both take the last place.
+    async fn check_capacity_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        resource_id: Uuid,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        exclude: Option<Uuid>,
+    ) -> Result<(), RepositoryError> {
+        let (capacity, active): (i32, bool) =
+            sqlx::query_as("SELECT capacity, active FROM resources WHERE id = $1 FOR UPDATE")
+                .bind(resource_id)
+                .fetch_optional(&mut **tx)
+                .await?
+                .ok_or(RepositoryError::ResourceNotFound(resource_id))?;
+
+        if !active {
+            return Err(RepositoryError::ResourceInactive(resource_id));
+        }
+
+        let blackout = sqlx::query_as::<_, BlackoutPeriod>(
+            "SELECT * FROM blackout_periods
+             WHERE (resource_id IS NULL OR resource_id = $1)
+             AND tstzrange($2, $3) && tstzrange(start_time, end_time)
+             ORDER BY start_time, id
+             LIMIT 1",
+        )
+        .bind(resource_id)
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        if let Some(blackout) = blackout {
+            return Err(RepositoryError::BlackedOut {
+                id: blackout.id,
+                reason: blackout.reason,
+            });
+        }
+
+        let overlapping: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM reservations
+             WHERE resource_id = $1
+             AND ($2::uuid IS NULL OR id <> $2)
+             AND status = ANY($5) AND (status <> 'pending' OR expires_at > NOW())
+             AND tstzrange($3, $4) && tstzrange(start_time, end_time)",
+        )
+        .bind(resource_id)
+        .bind(exclude)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(self.blocking_status_names())
+        .fetch_one(&mut **tx)
+        .await?;
+
+        if overlapping.0 >= capacity as i64 {
+            return Err(RepositoryError::ReservationConflict);
+        }
+
+        Ok(())
+    }
+
+    /// Fail with `HoldExpired` if `reservation` is a hold whose expiry has passed
+    ///
+    /// Lapsed holds stay pending until the background sweep marks them expired, but they
+    /// already stopped blocking their slot.
+    async fn check_hold_not_lapsed_tx(
+        tx: &mut Transaction<'_, Postgres>,
+        reservation: &Reservation,
+    ) -> Result<(), RepositoryError> {
+        if reservation.status != ReservationStatus::Pending {
+            return Ok(());
+        }
+
+        let (lapsed,): (bool,) = sqlx::query_as("SELECT COALESCE($1 <= NOW(), FALSE)")
+            .bind(reservation.expires_at)
+            .fetch_one(&mut **tx)
+            .await?;
+
+        if lapsed {
+            return Err(RepositoryError::HoldExpired(reservation.id));
+        }
+
+        Ok(())
+    }
+
+    /// Mark pending holds that expired at or before `now` as expired
+    ///
+    /// Returns the number of holds that were expired.
+    pub async fn expire_stale_holds(&self, now: DateTime<Utc>) -> Result<u64, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let expired: Vec<Uuid> = sqlx::query_as::<_, (Uuid,)>(
+            "UPDATE reservations SET status = 'expired'
+             WHERE status = 'pending' AND expires_at <= $1
+             RETURNING id",
+        )
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| row.0)
+        .collect();
+
+        if !expired.is_empty() {
+            Self::record_events_tx(
+                &mut tx,
+                &expired,
+                Some(ReservationStatus::Pending),
+                ReservationStatus::Expired,
+                None,
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(expired.len() as u64)
+    }
+
+    /// Mark confirmed reservations that ended at or before `now` as completed
+    ///
+    /// A single conditional update, so sweeps running on several instances at once never
+    /// complete a reservation twice or override a status set in the meantime. Returns the
+    /// number of reservations that were completed.
+    pub async fn complete_ended_reservations(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<u64, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let completed: Vec<Uuid> = sqlx::query_as::<_, (Uuid,)>(
+            "UPDATE reservations SET status = 'completed'
+             WHERE status = 'confirmed' AND end_time <= $1
+             RETURNING id",
+        )
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| row.0)
+        .collect();
+
+        if !completed.is_empty() {
+            Self::record_events_tx(
+                &mut tx,
+                &completed,
+                Some(ReservationStatus::Confirmed),
+                ReservationStatus::Completed,
+                None,
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(completed.len() as u64)
+    }
+
+    /// Confirm a pending hold, recording the change under `actor`
+    ///
+    /// Confirming an already confirmed reservation returns it unchanged.
+    pub async fn confirm_reservation(
+        &self,
+        id: Uuid,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let existing =
+            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        match existing.status {
+            ReservationStatus::Confirmed => return Ok(existing),
+            ReservationStatus::Cancelled => return Err(RepositoryError::ReservationCancelled(id)),
+            ReservationStatus::Expired => return Err(RepositoryError::HoldExpired(id)),
+            ReservationStatus::Pending => {}
+            status => return Err(RepositoryError::InvalidStatus { id, status }),
+        }
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "UPDATE reservations SET status = 'confirmed', expires_at = NULL
+             WHERE id = $1 AND expires_at > NOW()
+             RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|row| row.0)
+        .ok_or(RepositoryError::HoldExpired(id))?;
+
+        Self::record_events_tx(
+            &mut tx,
+            &[id],
+            Some(ReservationStatus::Pending),
+            ReservationStatus::Confirmed,
+            actor,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Look up the reservation created with an idempotency key within the retry window
+    ///
+    /// Keys older than the window are released so the client can reuse them.
+    async fn find_by_idempotency_key_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        client_id: Uuid,
+        key: &str,
+    ) -> Result<Option<Reservation>, RepositoryError> {
+        sqlx::query(
+            "UPDATE reservations SET idempotency_key = NULL
+             WHERE client_id = $1 AND idempotency_key = $2
+             AND created_at <= NOW() - make_interval(hours => $3)",
+        )
+        .bind(client_id)
+        .bind(key)
+        .bind(self.idempotency_key_ttl_hours)
+        .execute(&mut **tx)
+        .await?;
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "SELECT ",
+            reservation_with_attendees_columns!(),
+            " FROM reservations WHERE client_id = $1 AND idempotency_key = $2"
+        ))
+        .bind(client_id)
+        .bind(key)
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(|row| row.0);
+
+        Ok(reservation)
+    }
+
+    /// Get a reservation by its reference code, which must match exactly
+    pub async fn get_reservation_by_reference(
+        &self,
+        reference: &str,
+    ) -> Result<Reservation, RepositoryError> {
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "SELECT ",
+            reservation_with_attendees_columns!(),
+            " FROM reservations WHERE reference = $1"
+        ))
+        .bind(reference)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|row| row.0)
+        .ok_or_else(|| RepositoryError::ReservationReferenceNotFound(reference.to_string()))?;
+
+        Ok(reservation)
+    }
+
+    /// Get a reservation by ID
+    pub async fn get_reservation(&self, id: Uuid) -> Result<Reservation, RepositoryError> {
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "SELECT ",
+            reservation_with_attendees_columns!(),
+            " FROM reservations WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|row| row.0)
+        .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        Ok(reservation)
+    }
+
+    /// Get the audit trail of a reservation, oldest event first
+    pub async fn list_reservation_events(
+        &self,
+        reservation_id: Uuid,
+    ) -> Result<Vec<ReservationEvent>, RepositoryError> {
+        let exists = sqlx::query("SELECT 1 FROM reservations WHERE id = $1")
+            .bind(reservation_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+
+        if !exists {
+            return Err(RepositoryError::ReservationNotFound(reservation_id));
+        }
+
+        let events = sqlx::query_as::<_, ReservationEvent>(
+            "SELECT * FROM reservation_events
+             WHERE reservation_id = $1
+             ORDER BY created_at, id",
+        )
+        .bind(reservation_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Permanently remove a reservation, returning it as it was before deletion
+    ///
+    /// Unlike cancelling this leaves no record behind and frees the slot straight away.
+    pub async fn delete_reservation(&self, id: Uuid) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "DELETE FROM reservations WHERE id = $1 RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|row| row.0)
+        .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Cancel a reservation
+    ///
+    /// Pending holds can be cancelled as well. With `entire_series` every confirmed or
+    /// pending occurrence sharing the reservation's series is cancelled too. The optional
+    /// `reason` is stored on every reservation that gets cancelled, cancelling an already
+    /// cancelled reservation is a no-op and keeps the reason recorded the first time. Every
+    /// reservation that gets cancelled has an event naming `actor` added to its audit trail,
+    /// and the place it frees goes to the oldest waitlist entry that fits in its slot.
+    /// Fails with `VersionConflict` unless the reservation is still at `expected_version`, and
+    /// with `ReservationNotOwned` if `client_id` is given and the reservation isn't theirs.
+    pub async fn cancel_reservation(
+        &self,
+        id: Uuid,
+        client_id: Option<Uuid>,
+        expected_version: i32,
+        entire_series: bool,
+        reason: Option<&str>,
+        actor: Option<&str>,
+    ) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock what is about to be cancelled so each status change is recorded exactly once
+        let cancellable = sqlx::query_as::<_, Reservation>(
+            "SELECT * FROM reservations
+             WHERE status IN ('confirmed', 'pending')
+             AND (id = $1 OR ($2 AND series_id = (SELECT series_id FROM reservations WHERE id = $1)))
+             ORDER BY id
+             FOR UPDATE",
+        )
+        .bind(id)
+        .bind(entire_series)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let (version, owner) = match cancellable.iter().find(|res| res.id == id) {
+            Some(res) => (res.version, res.client_id),
+            None => {
+                // Not cancellable, check that the reservation exists
+                sqlx::query_as(
+                    "SELECT version, client_id FROM reservations WHERE id = $1 FOR UPDATE",
+                )
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?
+            }
+        };
+
+        if let Some(client_id) = client_id.filter(|client_id| *client_id != owner) {
+            return Err(RepositoryError::ReservationNotOwned { id, client_id });
+        }
+
+        if version != expected_version {
+            return Err(RepositoryError::VersionConflict(id));
+        }
+
+        if cancellable.is_empty() {
+            // If it exists but wasn't updated, it was already cancelled
+            return Ok(());
+        }
+
+        let ids: Vec<Uuid> = cancellable.iter().map(|res| res.id).collect();
+        sqlx::query(
+            "UPDATE reservations
+             SET status = 'cancelled', expires_at = NULL, cancellation_reason = $2
+             WHERE id = ANY($1)",
+        )
+        .bind(&ids)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?;
+
+        for old_status in [ReservationStatus::Confirmed, ReservationStatus::Pending] {
+            let changed: Vec<Uuid> = cancellable
+                .iter()
+                .filter(|res| res.status == old_status)
+                .map(|res| res.id)
+                .collect();
+
+            if !changed.is_empty() {
+                Self::record_events_tx(
+                    &mut tx,
+                    &changed,
+                    Some(old_status),
+                    ReservationStatus::Cancelled,
+                    actor,
+                )
+                .await?;
+            }
+        }
+
+        // Hand each freed place to whoever has been waiting for it longest
+        for freed in &cancellable {
+            self.promote_waitlist_tx(&mut tx, freed).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Turn the oldest waitlist entry that fits within a cancelled reservation's slot into a
+    /// confirmed reservation, returning it if there was one
+    ///
+    /// Entries are locked with `SKIP LOCKED`, so concurrent cancellations freeing overlapping
+    /// slots each promote a different entry and no entry is promoted twice. Entries whose slot
+    /// has started or is still full are passed over. Promotion bypasses the client's quota.
+    async fn promote_waitlist_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        freed: &Reservation,
+    ) -> Result<Option<Reservation>, RepositoryError> {
+        let mut passed_over: Vec<Uuid> = Vec::new();
+
+        loop {
+            let Some(entry) = sqlx::query_as::<_, WaitlistEntry>(
+                "SELECT * FROM waitlist
+                 WHERE resource_id = $1
+                 AND start_time >= $2 AND end_time <= $3
+                 AND start_time > NOW()
+                 AND NOT (id = ANY($4))
+                 ORDER BY created_at, id
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED",
+            )
+            .bind(freed.resource_id)
+            .bind(freed.start_time)
+            .bind(freed.end_time)
+            .bind(&passed_over)
+            .fetch_optional(&mut **tx)
+            .await?
+            else {
+                return Ok(None);
+            };
+
+            let new = NewReservation {
+                client_id: entry.client_id,
+                resource_id: entry.resource_id,
+                start_time: entry.start_time,
+                end_time: entry.end_time,
+                notes: None,
+                idempotency_key: None,
+                hold_expires_at: None,
+                attendee_client_ids: Vec::new(),
+            };
+
+            match self
+                .create_reservation_tx(tx, &new, None, Some(WAITLIST_ACTOR))
+                .await
+            {
+                Ok(reservation) => {
+                    sqlx::query("DELETE FROM waitlist WHERE id = $1")
+                        .bind(entry.id)
+                        .execute(&mut **tx)
+                        .await?;
+
+                    return Ok(Some(reservation));
+                }
+                Err(RepositoryError::ReservationConflict | RepositoryError::BlackedOut { .. }) => {
+                    passed_over.push(entry.id)
+                }
+                // Nobody can be booked onto a deactivated resource, the cancellation still goes ahead
+                Err(RepositoryError::ResourceInactive(_)) => return Ok(None),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Add a client to the waitlist for a slot on a resource
+    pub async fn join_waitlist(
+        &self,
+        client_id: Uuid,
+        resource_id: Uuid,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<WaitlistEntry, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let (archived_at,): (Option<DateTime<Utc>>,) =
+            sqlx::query_as("SELECT archived_at FROM clients WHERE id = $1 FOR SHARE")
+                .bind(client_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ClientNotFound(client_id))?;
+
+        if archived_at.is_some() {
+            return Err(RepositoryError::ClientArchived(client_id));
+        }
+
+        let (active,): (bool,) = sqlx::query_as("SELECT active FROM resources WHERE id = $1")
+            .bind(resource_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(RepositoryError::ResourceNotFound(resource_id))?;
+
+        if !active {
+            return Err(RepositoryError::ResourceInactive(resource_id));
+        }
+
+        let entry = sqlx::query_as::<_, WaitlistEntry>(
+            "INSERT INTO waitlist (client_id, resource_id, start_time, end_time)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+        )
+        .bind(client_id)
+        .bind(resource_id)
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(entry)
+    }
+
+    /// Get a client's waitlist entries, oldest first
+    pub async fn list_waitlist(
+        &self,
+        client_id: Uuid,
+    ) -> Result<Vec<WaitlistEntry>, RepositoryError> {
+        let client_exists = sqlx::query("SELECT 1 FROM clients WHERE id = $1")
+            .bind(client_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+
+        if !client_exists {
+            return Err(RepositoryError::ClientNotFound(client_id));
+        }
+
+        let entries = sqlx::query_as::<_, WaitlistEntry>(
+            "SELECT * FROM waitlist WHERE client_id = $1 ORDER BY created_at, id",
+        )
+        .bind(client_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Remove an entry from the waitlist
+    pub async fn leave_waitlist(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let rows_affected = sqlx::query("DELETE FROM waitlist WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(RepositoryError::WaitlistEntryNotFound(id));
+        }
+
+        Ok(())
+    }
+
+    /// Block `resource_id`, or every resource when `None`, from being booked in a period
+    ///
+    /// Reservations already overlapping the period are kept, `list_blackout_conflicts`
+    /// finds them.
+    pub async fn add_blackout(
+        &self,
+        resource_id: Option<Uuid>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<BlackoutPeriod, RepositoryError> {
+        if let Some(resource_id) = resource_id {
+            self.get_resource(resource_id).await?;
+        }
+
+        let blackout = sqlx::query_as::<_, BlackoutPeriod>(
+            "INSERT INTO blackout_periods (resource_id, start_time, end_time, reason)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+        )
+        .bind(resource_id)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(reason)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(blackout)
+    }
+
+    /// List blackouts ordered by start time, only those blocking `resource_id` when given
+    pub async fn list_blackouts(
+        &self,
+        resource_id: Option<Uuid>,
+    ) -> Result<Vec<BlackoutPeriod>, RepositoryError> {
+        let blackouts = sqlx::query_as::<_, BlackoutPeriod>(
+            "SELECT * FROM blackout_periods
+             WHERE $1::uuid IS NULL OR resource_id IS NULL OR resource_id = $1
+             ORDER BY start_time, id",
+        )
+        .bind(resource_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(blackouts)
+    }
+
+    /// Find the blackouts blocking any of `resource_ids` that overlap `[start, end)`
+    async fn find_blackouts(
+        &self,
+        resource_ids: &[Uuid],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<BlackoutPeriod>, RepositoryError> {
+        let blackouts = sqlx::query_as::<_, BlackoutPeriod>(
+            "SELECT * FROM blackout_periods
+             WHERE (resource_id IS NULL OR resource_id = ANY($1))
+             AND tstzrange($2, $3) && tstzrange(start_time, end_time)
+             ORDER BY start_time, id",
+        )
+        .bind(resource_ids)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(blackouts)
+    }
+
+    /// Remove a blackout, its period becomes bookable again
+    pub async fn remove_blackout(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let rows_affected = sqlx::query("DELETE FROM blackout_periods WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(RepositoryError::BlackoutNotFound(id));
+        }
+
+        Ok(())
+    }
+
+    /// Get the reservations taking up a slot during a blackout, ordered by start time
+    ///
+    /// These were booked before the blackout was added and are left for staff to resolve.
+    pub async fn list_blackout_conflicts(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<Reservation>, RepositoryError> {
+        let blackout =
+            sqlx::query_as::<_, BlackoutPeriod>("SELECT * FROM blackout_periods WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or(RepositoryError::BlackoutNotFound(id))?;
+
+        let reservations = sqlx::query_as::<_, WithAttendees>(concat!(
+            "SELECT ",
+            reservation_with_attendees_columns!(),
+            " FROM reservations
+             WHERE ($1::uuid IS NULL OR resource_id = $1)
+             AND status = ANY($4) AND (status <> 'pending' OR expires_at > NOW())
+             AND tstzrange(start_time, end_time) && tstzrange($2, $3)
+             ORDER BY start_time, id"
+        ))
+        .bind(blackout.resource_id)
+        .bind(blackout.start_time)
+        .bind(blackout.end_time)
+        .bind(self.blocking_status_names())
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.0)
+        .collect();
+
+        Ok(reservations)
+    }
+
+    /// Record that the client arrived for a confirmed reservation at `at`
+    ///
+    /// Check-in is allowed from 30 minutes before the start until the end. Checking in
+    /// again returns the reservation with the time recorded the first time.
+    pub async fn check_in(
+        &self,
+        id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let existing =
+            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        match existing.status {
+            ReservationStatus::Cancelled => return Err(RepositoryError::ReservationCancelled(id)),
+            ReservationStatus::Confirmed => {}
+            status => return Err(RepositoryError::InvalidStatus { id, status }),
+        }
+
+        if existing.checked_in_at.is_some() {
+            return Ok(existing);
+        }
+
+        let opens_at = existing.start_time - chrono::Duration::minutes(CHECK_IN_EARLY_MINUTES);
+        if at < opens_at || at > existing.end_time {
+            return Err(RepositoryError::OutsideCheckInWindow(id));
+        }
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "UPDATE reservations SET checked_in_at = $2 WHERE id = $1 RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .bind(at)
+        .fetch_one(&mut *tx)
+        .await?
+        .0;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Record that the client didn't turn up for a confirmed reservation that has ended
+    ///
+    /// Reservations the completion sweep already marked as completed can still be marked
+    /// unless the client checked in. Marking a reservation that is already a no-show returns
+    /// it unchanged. The change is recorded under `actor`.
+    pub async fn mark_no_show(
+        &self,
+        id: Uuid,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let existing =
+            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        match existing.status {
+            ReservationStatus::NoShow => return Ok(existing),
+            ReservationStatus::Cancelled => return Err(RepositoryError::ReservationCancelled(id)),
+            ReservationStatus::Confirmed => {}
+            ReservationStatus::Completed if existing.checked_in_at.is_none() => {}
+            status => return Err(RepositoryError::InvalidStatus { id, status }),
+        }
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "UPDATE reservations SET status = 'no_show'
+             WHERE id = $1 AND end_time <= NOW()
+             RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|row| row.0)
+        .ok_or(RepositoryError::ReservationNotEnded(id))?;
+
+        Self::record_events_tx(
+            &mut tx,
+            &[id],
+            Some(existing.status),
+            ReservationStatus::NoShow,
+            actor,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Reinstate a cancelled reservation as confirmed
+    ///
+    /// Fails with `ReservationConflict` if its slot has been booked by someone else since it
+    /// was cancelled. Reinstating an already confirmed reservation returns it unchanged. The
+    /// change is recorded under `actor`.
+    pub async fn reinstate_reservation(
+        &self,
+        id: Uuid,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the client before the reservation, in the same order as `delete_client`
+        let (client_id,): (Uuid,) =
+            sqlx::query_as("SELECT client_id FROM reservations WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+        sqlx::query("SELECT 1 FROM clients WHERE id = $1 FOR UPDATE")
+            .bind(client_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Lock the reservation so concurrent reinstates can't interleave
+        let existing =
+            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        match existing.status {
+            ReservationStatus::Confirmed => return Ok(existing),
+            ReservationStatus::Cancelled => {}
+            status => return Err(RepositoryError::InvalidStatus { id, status }),
+        }
+
+        // A reinstated reservation counts towards the quota again
+        if existing.end_time > Utc::now() {
+            self.check_quota_tx(&mut tx, client_id, 1).await?;
+        }
+
+        // Check whether other reservations have taken up the slot in the meantime
+        self.check_capacity_tx(
+            &mut tx,
+            existing.resource_id,
+            existing.start_time,
+            existing.end_time,
+            Some(id),
+        )
+        .await?;
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "UPDATE reservations SET status = 'confirmed', cancellation_reason = NULL
+             WHERE id = $1
+             RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?
+        .0;
+
+        Self::record_events_tx(
+            &mut tx,
+            &[id],
+            Some(ReservationStatus::Cancelled),
+            ReservationStatus::Confirmed,
+            actor,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Replace the notes on a reservation that isn't cancelled, `None` clears them
+    pub async fn update_notes(
+        &self,
+        id: Uuid,
+        notes: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the reservation so it can't be cancelled between the check and the update
+        let existing =
+            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        if existing.status == ReservationStatus::Cancelled {
+            return Err(RepositoryError::ReservationCancelled(id));
+        }
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "UPDATE reservations SET notes = $2 WHERE id = $1 RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .bind(notes)
+        .fetch_one(&mut *tx)
+        .await?
+        .0;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Push back the end of a confirmed reservation or pending hold to `end_time`
+    ///
+    /// The extension is recorded in the reservation's audit trail under `actor`. Fails with
+    /// `EndNotExtended` unless `end_time` is later than the current end, and with
+    /// `ReservationConflict` if the resource is fully booked for the added time.
+    pub async fn extend_reservation(
+        &self,
+        id: Uuid,
+        end_time: DateTime<Utc>,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the reservation so concurrent updates can't interleave
+        let existing =
+            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        match existing.status {
+            ReservationStatus::Cancelled => return Err(RepositoryError::ReservationCancelled(id)),
+            ReservationStatus::Expired => return Err(RepositoryError::HoldExpired(id)),
+            ReservationStatus::Confirmed | ReservationStatus::Pending => {}
+            status => return Err(RepositoryError::InvalidStatus { id, status }),
+        }
+        Self::check_hold_not_lapsed_tx(&mut tx, &existing).await?;
+
+        if end_time <= existing.end_time {
+            return Err(RepositoryError::EndNotExtended {
+                id,
+                end_time: existing.end_time,
+            });
+        }
+
+        // Only the added time needs checking, the existing slot is already held
+        self.check_capacity_tx(
+            &mut tx,
+            existing.resource_id,
+            existing.end_time,
+            end_time,
+            Some(id),
+        )
+        .await?;
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "UPDATE reservations SET end_time = $2 WHERE id = $1 RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .bind(end_time)
+        .fetch_one(&mut *tx)
+        .await?
+        .0;
+
+        // The status is unchanged, the event records that the reservation was extended
+        Self::record_events_tx(
+            &mut tx,
+            &[id],
+            Some(existing.status),
+            reservation.status,
+            actor,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Move a confirmed reservation or pending hold to a new time slot
+    ///
+    /// The move is recorded in the reservation's audit trail under `actor`. Fails with
+    /// `VersionConflict` unless the reservation is still at `expected_version`.
+    pub async fn reschedule_reservation(
+        &self,
+        id: Uuid,
+        expected_version: i32,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        actor: Option<&str>,
+    ) -> Result<Reservation, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the reservation so concurrent updates can't interleave
+        let existing =
+            sqlx::query_as::<_, Reservation>("SELECT * FROM reservations WHERE id = $1 FOR UPDATE")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(RepositoryError::ReservationNotFound(id))?;
+
+        if existing.version != expected_version {
+            return Err(RepositoryError::VersionConflict(id));
+        }
+
+        match existing.status {
+            ReservationStatus::Cancelled => return Err(RepositoryError::ReservationCancelled(id)),
+            ReservationStatus::Expired => return Err(RepositoryError::HoldExpired(id)),
+            ReservationStatus::Confirmed | ReservationStatus::Pending => {}
+            status => return Err(RepositoryError::InvalidStatus { id, status }),
+        }
+        Self::check_hold_not_lapsed_tx(&mut tx, &existing).await?;
+
+        // Check the new slot against other reservations on the resource, excluding this one
+        self.check_capacity_tx(
+            &mut tx,
+            existing.resource_id,
+            start_time,
+            end_time,
+            Some(id),
+        )
+        .await?;
+
+        let reservation = sqlx::query_as::<_, WithAttendees>(concat!(
+            "UPDATE reservations SET start_time = $2, end_time = $3 WHERE id = $1 RETURNING ",
+            reservation_with_attendees_columns!()
+        ))
+        .bind(id)
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_one(&mut *tx)
+        .await?
+        .0;
+
+        // The status is unchanged, the event records that the reservation was moved
+        Self::record_events_tx(
+            &mut tx,
+            &[id],
+            Some(existing.status),
+            reservation.status,
+            actor,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(reservation)
+    }
+
+    /// Get a page of reservations for a client ordered by start time
+    ///
+    /// Fails with `ClientNotFound` if the client doesn't exist, otherwise behaves like
+    /// `list_reservations` restricted to the client. Reservations the client only attends
+    /// are included when `filter.include_attended` is set.
+    pub async fn get_client_reservations(
+        &self,
+        client_id: Uuid,
+        filter: &ReservationFilter,
+        limit: i64,
+        after: Option<PageCursor>,
+    ) -> Result<Page<Reservation>, RepositoryError> {
+        // Check if client exists
+        let client_exists = sqlx::query("SELECT 1 FROM clients WHERE id = $1")
+            .bind(client_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+
+        if !client_exists {
+            return Err(RepositoryError::ClientNotFound(client_id));
+        }
+
+        let filter = ReservationFilter {
+            client_id: Some(client_id),
+            ..filter.clone()
+        };
+
+        self.list_reservations(&filter, limit, after).await
+    }
+
+    /// Get a page of reservations across all clients ordered by start time
+    ///
+    /// Returns at most `limit` reservations matching `filter` positioned strictly after `after`.
+    /// The time range uses the same overlap semantics as `find_available_slots`, so a
+    /// reservation straddling either bound is included.
+    pub async fn list_reservations(
+        &self,
+        filter: &ReservationFilter,
+        limit: i64,
+        after: Option<PageCursor>,
+    ) -> Result<Page<Reservation>, RepositoryError> {
+        // Fetch one extra row to find out whether there is another page
+        let rows = sqlx::query_as::<_, WithAttendees>(concat!(
+            "SELECT ",
+            reservation_with_attendees_columns!(),
+            " FROM reservations
+             WHERE ($1::uuid IS NULL OR client_id = $1 OR ($9 AND id IN (
+                SELECT reservation_id FROM reservation_attendees WHERE client_id = $1)))
+             AND ($2::text IS NULL OR status = $2)
+             AND tstzrange(start_time, end_time) && tstzrange($3::timestamptz, $4::timestamptz)
+             AND ($5::uuid IS NULL OR resource_id = $5)
+             AND ($6::timestamptz IS NULL OR (start_time, id) > ($6, $7))
+             ORDER BY start_time, id
+             LIMIT $8"
+        ))
+        .bind(filter.client_id)
+        .bind(filter.status.map(String::from))
+        .bind(filter.start_time)
+        .bind(filter.end_time)
+        .bind(filter.resource_id)
+        .bind(after.as_ref().map(|cursor| cursor.timestamp))
+        .bind(after.as_ref().map(|cursor| cursor.id))
+        .bind(limit + 1)
+        .bind(filter.include_attended)
+        .fetch_all(&self.pool)
+        .await?;
+        let reservations = rows.into_iter().map(|row| row.0).collect();
+
+        Ok(Page::from_rows(reservations, limit, |res| PageCursor {
+            timestamp: res.start_time,
+            id: res.id,
+        }))
+    }
+
+    /// Count a client's reservations by status in a single pass over them
+    ///
+    /// Fails with `ClientNotFound` if the client doesn't exist, a client without any
+    /// reservations gets all zeros.
+    pub async fn get_client_stats(&self, client_id: Uuid) -> Result<ClientStats, RepositoryError> {
+        let stats = sqlx::query_as::<_, ClientStats>(
+            "SELECT
+                COUNT(r.id) AS total,
+                COUNT(*) FILTER (WHERE r.status = 'confirmed' AND r.start_time > NOW()) AS upcoming,
+                COUNT(*) FILTER (WHERE r.status = 'cancelled') AS cancelled,
+                COUNT(*) FILTER (WHERE r.status = 'completed') AS completed,
+                COUNT(*) FILTER (WHERE r.status = 'no_show') AS no_shows
+             FROM clients c
+             LEFT JOIN reservations r ON r.client_id = c.id
+             WHERE c.id = $1
+             GROUP BY c.id",
+        )
+        .bind(client_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(RepositoryError::ClientNotFound(client_id))?;
+
+        Ok(stats)
+    }
+
+    /// Count reservations across all clients matching `filter`
+    ///
+    /// Uses the same filter semantics as `list_reservations`.
+    pub async fn count_reservations(
+        &self,
+        filter: &ReservationFilter,
+    ) -> Result<i64, RepositoryError> {
+        let count: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM reservations
+             WHERE ($1::uuid IS NULL OR client_id = $1 OR ($6 AND id IN (
+                SELECT reservation_id FROM reservation_attendees WHERE client_id = $1)))
+             AND ($2::text IS NULL OR status = $2)
+             AND tstzrange(start_time, end_time) && tstzrange($3::timestamptz, $4::timestamptz)
+             AND ($5::uuid IS NULL OR resource_id = $5)",
+        )
+        .bind(filter.client_id)
+        .bind(filter.status.map(String::from))
+        .bind(filter.start_time)
+        .bind(filter.end_time)
+        .bind(filter.resource_id)
+        .bind(filter.include_attended)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count.0)
+    }
+
+    /// Stream reservations across all clients ordered by start time
+    ///
+    /// Rows are read from a database cursor as the stream is polled, dropping the
+    /// stream stops the query.
+    pub fn stream_reservations(
+        &self,
+        filter: &ReservationFilter,
+    ) -> impl Stream<Item = Result<Reservation, RepositoryError>> + '_ {
+        sqlx::query_as::<_, WithAttendees>(concat!(
+            "SELECT ",
+            reservation_with_attendees_columns!(),
+            " FROM reservations
+             WHERE ($1::uuid IS NULL OR client_id = $1 OR ($6 AND id IN (
+                SELECT reservation_id FROM reservation_attendees WHERE client_id = $1)))
+             AND ($2::text IS NULL OR status = $2)
+             AND tstzrange(start_time, end_time) && tstzrange($3::timestamptz, $4::timestamptz)
+             AND ($5::uuid IS NULL OR resource_id = $5)
+             ORDER BY start_time, id"
+        ))
+        .bind(filter.client_id)
+        .bind(filter.status.map(String::from))
+        .bind(filter.start_time)
+        .bind(filter.end_time)
+        .bind(filter.resource_id)
+        .bind(filter.include_attended)
+        .fetch(&self.pool)
+        .map(|result| result.map(|row| row.0).map_err(RepositoryError::from))
+    }
+
+    /// Start listening for committed reservation changes on a dedicated connection
+    ///
+    /// Every insert or update of a reservation is announced by a database trigger, whichever
+    /// repository method made it.
+    pub async fn listen_for_changes(
+        &self,
+    ) -> Result<ReservationChangeListener<'_>, RepositoryError> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(RESERVATION_CHANGES_CHANNEL).await?;
+
+        Ok(ReservationChangeListener {
+            listener,
+            repository: self,
+        })
+    }
+}
+
+/// Receives reservation changes announced by the database
+pub struct ReservationChangeListener<'a> {
+    listener: PgListener,
+    repository: &'a ReservationRepository,
+}
+
+impl ReservationChangeListener<'_> {
+    /// Wait for the next change
+    ///
+    /// The reservation is read once the notification arrives, so it reflects the latest
+    /// state rather than the one right after the change. Changes to reservations deleted
+    /// in the meantime are skipped. Notifications sent while the connection was being
+    /// re-established are lost.
+    pub async fn recv(&mut self) -> Result<ReservationChange, RepositoryError> {
+        loop {
+            let notification = self.listener.recv().await?;
+
+            let Some((kind, id)) = notification.payload().split_once(':') else {
+                continue;
+            };
+            let kind = match kind {
+                "created" => ReservationChangeKind::Created,
+                "cancelled" => ReservationChangeKind::Cancelled,
+                "updated" => ReservationChangeKind::Updated,
+                _ => continue,
+            };
+            let Ok(id) = id.parse::<Uuid>() else {
+                continue;
+            };
+
+            match self.repository.get_reservation(id).await {
+                Ok(reservation) => return Ok(ReservationChange { kind, reservation }),
+                Err(RepositoryError::ReservationNotFound(_)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDatabase;
+    use crate::db::DEFAULT_RESOURCE_ID;
+    use chrono::Duration;
+
+    async fn setup() -> (TestDatabase, ReservationRepository) {
+        let db = TestDatabase::new().await;
+        let repository = ReservationRepository::new(db.pool.clone());
+        (db, repository)
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2030, 1, 7, hour, 0, 0).unwrap()
+    }
+
+    /// A reservation of the default resource, which has room for one at a time
+    fn new_reservation(client_id: Uuid, start_hour: u32, end_hour: u32) -> NewReservation {
+        NewReservation {
+            client_id,
+            resource_id: DEFAULT_RESOURCE_ID,
+            start_time: at(start_hour),
+            end_time: at(end_hour),
+            notes: None,
+            idempotency_key: None,
+            hold_expires_at: None,
+            attendee_client_ids: Vec::new(),
+        }
+    }
+
+    async fn create_client(repository: &ReservationRepository, email: &str) -> Client {
+        repository
+            .create_client("Test Client", email, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn client_emails_are_stored_lowercased_and_match_ignoring_case() {
+        let (_db, repository) = setup().await;
+        let client = create_client(&repository, " Alice.Smith@Example.com ").await;
+        assert_eq!(client.email, "alice.smith@example.com");
+
+        let found = repository
+            .find_client_by_email("alice.smith@EXAMPLE.com")
+            .await
+            .unwrap();
+        assert_eq!(found.id, client.id);
+
+        let err = repository
+            .create_client("Other Client", "ALICE.SMITH@example.com", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::DuplicateEmail(_)));
+    }
+
+    #[tokio::test]
+    async fn name_sorted_pages_continue_after_the_last_client_changes() {
+        let (_db, repository) = setup().await;
+        let mut ids = Vec::new();
+        for (name, email) in [
+            ("Alice", "alice@example.com"),
+            ("Bob", "bob@example.com"),
+            ("Carol", "carol@example.com"),
+        ] {
+            let client = repository.create_client(name, email, None).await.unwrap();
+            ids.push(client.id);
+        }
+        let sort = ClientSort {
+            field: ClientSortField::Name,
+            descending: false,
+        };
+
+        let first = repository.list_clients(1, None, sort, false).await.unwrap();
+        assert_eq!(first.items[0].id, ids[0]);
+
+        // Renaming the cursor's client must not move the next page
+        repository
+            .update_client(ids[0], Some("Zoe"), None, None)
+            .await
+            .unwrap();
+        let second = repository
+            .list_clients(1, first.next_cursor, sort, false)
+            .await
+            .unwrap();
+        assert_eq!(second.items[0].id, ids[1]);
+
+        // Nor must deleting it
+        repository
+            .delete_client(ids[1], ClientDeletionMode::Refuse, None)
+            .await
+            .unwrap();
+        let third = repository
+            .list_clients(1, second.next_cursor, sort, false)
+            .await
+            .unwrap();
+        assert_eq!(third.items[0].id, ids[2]);
+    }
+
+    #[tokio::test]
+    async fn archived_clients_are_hidden_but_keep_their_reservations() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+
+        let archived = repository.archive_client(alice.id).await.unwrap();
+        assert!(archived.archived_at.is_some());
+
+        let listed = repository
+            .list_clients(10, None, ClientSort::default(), false)
+            .await
+            .unwrap();
+        let ids: Vec<_> = listed.items.iter().map(|client| client.id).collect();
+        assert_eq!(ids, vec![bob.id]);
+
+        let listed = repository
+            .list_clients(10, None, ClientSort::default(), true)
+            .await
+            .unwrap();
+        assert_eq!(listed.items.len(), 2);
+
+        let found = repository.search_clients("example", 10).await.unwrap();
+        let ids: Vec<_> = found.iter().map(|client| client.id).collect();
+        assert_eq!(ids, vec![bob.id]);
+
+        // Their history is still there for reporting
+        let history = repository
+            .get_client_reservations(alice.id, &ReservationFilter::default(), 10, None)
+            .await
+            .unwrap();
+        let ids: Vec<_> = history.items.iter().map(|res| res.id).collect();
+        assert_eq!(ids, vec![reservation.id]);
+        assert_eq!(
+            repository.get_reservation(reservation.id).await.unwrap().id,
+            reservation.id
+        );
+        assert_eq!(
+            repository.get_client_stats(alice.id).await.unwrap().total,
+            1
+        );
+
+        let err = repository
+            .create_reservation(&new_reservation(alice.id, 12, 13), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ClientArchived(id) if id == alice.id));
+
+        repository.unarchive_client(alice.id).await.unwrap();
+        repository
+            .create_reservation(&new_reservation(alice.id, 12, 13), None)
+            .await
+            .unwrap();
+        let found = repository.search_clients("alice", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_client_is_refused_while_they_hold_a_live_hold() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        repository
+            .create_reservation(
+                &NewReservation {
+                    hold_expires_at: Some(Utc::now() + Duration::minutes(10)),
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let err = repository
+            .delete_client(alice.id, ClientDeletionMode::Refuse, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RepositoryError::ClientHasUpcomingReservations { id, count: 1 } if id == alice.id
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleting_a_client_is_refused_with_the_count_of_upcoming_reservations() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        for (start_hour, end_hour) in [(10, 11), (12, 13)] {
+            repository
+                .create_reservation(&new_reservation(alice.id, start_hour, end_hour), None)
+                .await
+                .unwrap();
+        }
+
+        let err = repository
+            .delete_client(alice.id, ClientDeletionMode::Refuse, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RepositoryError::ClientHasUpcomingReservations { id, count: 2 } if id == alice.id
+        ));
+
+        let kept = repository.get_client(alice.id).await.unwrap();
+        assert_eq!(kept.email, "alice@example.com");
+    }
+
+    #[tokio::test]
+    async fn deleting_a_client_with_only_past_reservations_anonymizes_them() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let past = repository
+            .create_reservation(
+                &NewReservation {
+                    start_time: Utc::now() - Duration::hours(3),
+                    end_time: Utc::now() - Duration::hours(2),
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        repository
+            .delete_client(alice.id, ClientDeletionMode::Refuse, None)
+            .await
+            .unwrap();
+
+        let anonymized = repository.get_client(alice.id).await.unwrap();
+        assert_eq!(anonymized.name, "Deleted client");
+        assert_eq!(anonymized.email, format!("deleted-{}@invalid", alice.id));
+        assert_eq!(anonymized.phone, None);
+
+        let kept = repository.get_reservation(past.id).await.unwrap();
+        assert_eq!(kept.client_id, alice.id);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_client_in_cascade_mode_cancels_upcoming_and_keeps_history() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let past = repository
+            .create_reservation(
+                &NewReservation {
+                    start_time: Utc::now() - Duration::hours(3),
+                    end_time: Utc::now() - Duration::hours(2),
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let upcoming = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        let attended = repository
+            .create_reservation(
+                &NewReservation {
+                    attendee_client_ids: vec![alice.id],
+                    ..new_reservation(bob.id, 12, 13)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        repository
+            .join_waitlist(bob.id, DEFAULT_RESOURCE_ID, at(10), at(11))
+            .await
+            .unwrap();
+
+        repository
+            .delete_client(alice.id, ClientDeletionMode::Cascade, Some("admin"))
+            .await
+            .unwrap();
+
+        let anonymized = repository.get_client(alice.id).await.unwrap();
+        assert_eq!(anonymized.name, "Deleted client");
+
+        let kept = repository.get_reservation(past.id).await.unwrap();
+        assert_eq!(kept.status, ReservationStatus::Confirmed);
+
+        let cancelled = repository.get_reservation(upcoming.id).await.unwrap();
+        assert_eq!(cancelled.status, ReservationStatus::Cancelled);
+        let events = repository
+            .list_reservation_events(upcoming.id)
+            .await
+            .unwrap();
+        let last = events.last().unwrap();
+        assert_eq!(last.new_status, ReservationStatus::Cancelled);
+        assert_eq!(last.actor.as_deref(), Some("admin"));
+
+        // Bob's group reservation stays, without Alice
+        let attended = repository.get_reservation(attended.id).await.unwrap();
+        assert_eq!(attended.status, ReservationStatus::Confirmed);
+        assert!(attended.attendee_client_ids.is_empty());
+
+        // The cancelled place went to the waitlist
+        let stats = repository.get_client_stats(bob.id).await.unwrap();
+        assert_eq!(stats.upcoming, 2);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_client_without_reservations_removes_them() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+
+        repository
+            .delete_client(alice.id, ClientDeletionMode::Cascade, None)
+            .await
+            .unwrap();
+
+        let err = repository.get_client(alice.id).await.unwrap_err();
+        assert!(matches!(err, RepositoryError::ClientNotFound(id) if id == alice.id));
+    }
+
+    #[tokio::test]
+    async fn create_reservation_is_confirmed_and_can_be_read_back() {
+        let (_db, repository) = setup().await;
+        let client = create_client(&repository, "alice@example.com").await;
+
+        let created = repository
+            .create_reservation(&new_reservation(client.id, 10, 11), Some("test"))
+            .await
+            .unwrap();
+
+        assert_eq!(created.status, ReservationStatus::Confirmed);
+        assert_eq!(created.client_id, client.id);
+        assert_eq!(created.start_time, at(10));
+        assert_eq!(created.end_time, at(11));
+        assert_eq!(created.version, 1);
+
+        let fetched = repository.get_reservation(created.id).await.unwrap();
+        assert_eq!(fetched.id, created.id);
+        assert_eq!(fetched.reference, created.reference);
+    }
+
+    fn keyed_reservation(client_id: Uuid, start_hour: u32, end_hour: u32) -> NewReservation {
+        NewReservation {
+            idempotency_key: Some("retry-key".to_string()),
+            ..new_reservation(client_id, start_hour, end_hour)
+        }
+    }
+
+    #[tokio::test]
+    async fn retrying_with_an_idempotency_key_returns_the_same_reservation() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+
+        let first = repository
+            .create_reservation(&keyed_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        let retried = repository
+            .create_reservation(&keyed_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+
+        assert_eq!(retried.id, first.id);
+        let stats = repository.get_client_stats(alice.id).await.unwrap();
+        assert_eq!(stats.total, 1);
+    }
+
+    #[tokio::test]
+    async fn idempotency_keys_are_scoped_to_the_client() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+
+        let alices = repository
+            .create_reservation(&keyed_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        let bobs = repository
+            .create_reservation(&keyed_reservation(bob.id, 12, 13), None)
+            .await
+            .unwrap();
+
+        assert_ne!(bobs.id, alices.id);
+        assert_eq!(bobs.client_id, bob.id);
+    }
+
+    #[tokio::test]
+    async fn idempotency_keys_older_than_the_window_no_longer_match() {
+        let (db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+
+        let first = repository
+            .create_reservation(&keyed_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE reservations SET created_at = NOW() - make_interval(hours => $2 + 1)
+             WHERE id = $1",
+        )
+        .bind(first.id)
+        .bind(DEFAULT_IDEMPOTENCY_KEY_TTL_HOURS)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let second = repository
+            .create_reservation(&keyed_reservation(alice.id, 12, 13), None)
+            .await
+            .unwrap();
+
+        assert_ne!(second.id, first.id);
+        assert_eq!(second.start_time, at(12));
+    }
+
+    #[tokio::test]
+    async fn batch_skipping_failures_creates_the_rest_with_the_single_create_rules() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        repository
+            .add_blackout(None, at(14), at(15), "Maintenance")
+            .await
+            .unwrap();
+
+        let items = vec![
+            keyed_reservation(alice.id, 10, 11),
+            new_reservation(bob.id, 10, 11),
+            NewReservation {
+                hold_expires_at: Some(Utc::now() + Duration::minutes(10)),
+                attendee_client_ids: vec![alice.id],
+                ..new_reservation(bob.id, 12, 13)
+            },
+            new_reservation(bob.id, 14, 15),
+            keyed_reservation(alice.id, 10, 11),
+        ];
+        let results = repository
+            .create_reservations(&items, true, None)
+            .await
+            .unwrap();
+
+        let first = results[0].as_ref().unwrap();
+        assert!(matches!(
+            results[1],
+            Err(RepositoryError::ReservationConflict)
+        ));
+        let hold = results[2].as_ref().unwrap();
+        assert_eq!(hold.status, ReservationStatus::Pending);
+        assert_eq!(hold.attendee_client_ids, vec![alice.id]);
+        assert!(matches!(
+            results[3],
+            Err(RepositoryError::BlackedOut { .. })
+        ));
+        // A key repeated within the batch returns the reservation it created
+        assert_eq!(results[4].as_ref().unwrap().id, first.id);
+
+        let stats = repository.get_client_stats(bob.id).await.unwrap();
+        assert_eq!(stats.total, 1);
+    }
+
+    #[tokio::test]
+    async fn batch_without_skipping_failures_creates_nothing_on_a_failure() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+
+        let items = vec![
+            new_reservation(alice.id, 10, 11),
+            new_reservation(bob.id, 10, 11),
+        ];
+        let err = repository
+            .create_reservations(&items, false, None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            RepositoryError::BatchItemFailed { index: 1, ref source }
+                if matches!(**source, RepositoryError::ReservationConflict)
+        ));
+        let stats = repository.get_client_stats(alice.id).await.unwrap();
+        assert_eq!(stats.total, 0);
+    }
+
+    /// Weekly slots from `at(start_hour)` to `at(end_hour)`
+    fn weekly(start_hour: u32, end_hour: u32, count: i64) -> Vec<TimeSlot> {
+        (0..count)
+            .map(|week| TimeSlot {
+                start_time: at(start_hour) + Duration::weeks(week),
+                end_time: at(end_hour) + Duration::weeks(week),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn blacked_out_occurrences_fail_the_series_unless_skipped() {
+        let (_db, repository) = setup().await;
+        let client = create_client(&repository, "alice@example.com").await;
+        repository
+            .add_blackout(
+                None,
+                at(9) + Duration::weeks(1),
+                at(12) + Duration::weeks(1),
+                "Maintenance",
+            )
+            .await
+            .unwrap();
+        let occurrences = weekly(10, 11, 3);
+
+        let err = repository
+            .create_recurring_reservation(
+                &new_reservation(client.id, 10, 11),
+                &occurrences,
+                false,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RepositoryError::SeriesConflict { occurrence: 2, start_time }
+                if start_time == occurrences[1].start_time
+        ));
+        let stats = repository.get_client_stats(client.id).await.unwrap();
+        assert_eq!(stats.total, 0);
+
+        let series = repository
+            .create_recurring_reservation(
+                &new_reservation(client.id, 10, 11),
+                &occurrences,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(series.reservations.len(), 2);
+        assert_eq!(series.skipped.len(), 1);
+        assert_eq!(series.skipped[0].start_time, occurrences[1].start_time);
+    }
+
+    #[tokio::test]
+    async fn only_created_occurrences_count_towards_the_quota() {
+        let (_db, repository) = setup().await;
+        let repository = repository.with_reservation_quota(Some(2));
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let occurrences = weekly(10, 11, 3);
+        repository
+            .create_reservation(
+                &NewReservation {
+                    start_time: occurrences[1].start_time,
+                    end_time: occurrences[1].end_time,
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // The taken occurrence is skipped, leaving two within the quota
+        let series = repository
+            .create_recurring_reservation(
+                &new_reservation(bob.id, 10, 11),
+                &occurrences,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(series.reservations.len(), 2);
+
+        let err = repository
+            .create_recurring_reservation(
+                &new_reservation(alice.id, 14, 15),
+                &weekly(14, 15, 2),
+                false,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RepositoryError::QuotaExceeded {
+                count: 1,
+                quota: 2,
+                ..
+            }
+        ));
+        let stats = repository.get_client_stats(alice.id).await.unwrap();
+        assert_eq!(stats.total, 1);
+    }
+
+    #[tokio::test]
+    async fn create_reservation_for_unknown_client_fails() {
+        let (_db, repository) = setup().await;
+        let client_id = Uuid::new_v4();
+
+        let err = repository
+            .create_reservation(&new_reservation(client_id, 10, 11), None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RepositoryError::ClientNotFound(id) if id == client_id));
+    }
+
+    #[tokio::test]
+    async fn reservations_are_read_back_with_their_attendees() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let created = repository
+            .create_reservation(
+                &NewReservation {
+                    attendee_client_ids: vec![bob.id],
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(created.attendee_client_ids, vec![bob.id]);
+
+        let fetched = repository.get_reservation(created.id).await.unwrap();
+        assert_eq!(fetched.attendee_client_ids, vec![bob.id]);
+        let fetched = repository
+            .get_reservation_by_reference(&created.reference)
+            .await
+            .unwrap();
+        assert_eq!(fetched.attendee_client_ids, vec![bob.id]);
+
+        let filter = ReservationFilter {
+            client_id: Some(bob.id),
+            include_attended: true,
+            ..Default::default()
+        };
+        let page = repository
+            .list_reservations(&filter, 10, None)
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].attendee_client_ids, vec![bob.id]);
+    }
+
+    #[tokio::test]
+    async fn changed_reservations_are_returned_with_their_attendees() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let hold = repository
+            .create_reservation(
+                &NewReservation {
+                    hold_expires_at: Some(Utc::now() + Duration::minutes(10)),
+                    attendee_client_ids: vec![bob.id],
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let id = hold.id;
+
+        let confirmed = repository.confirm_reservation(id, None).await.unwrap();
+        assert_eq!(confirmed.attendee_client_ids, vec![bob.id]);
+        let noted = repository
+            .update_notes(id, Some("Bring a racket"))
+            .await
+            .unwrap();
+        assert_eq!(noted.attendee_client_ids, vec![bob.id]);
+        let extended = repository
+            .extend_reservation(id, at(12), None)
+            .await
+            .unwrap();
+        assert_eq!(extended.attendee_client_ids, vec![bob.id]);
+        let moved = repository
+            .reschedule_reservation(id, extended.version, at(13), at(14), None)
+            .await
+            .unwrap();
+        assert_eq!(moved.attendee_client_ids, vec![bob.id]);
+
+        repository
+            .cancel_reservation(id, None, moved.version, false, None, None)
+            .await
+            .unwrap();
+        let reinstated = repository.reinstate_reservation(id, None).await.unwrap();
+        assert_eq!(reinstated.attendee_client_ids, vec![bob.id]);
+
+        let blackout = repository
+            .add_blackout(None, at(13), at(14), "Maintenance")
+            .await
+            .unwrap();
+        let conflicts = repository
+            .list_blackout_conflicts(blackout.id)
+            .await
+            .unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].attendee_client_ids, vec![bob.id]);
+
+        let deleted = repository.delete_reservation(id).await.unwrap();
+        assert_eq!(deleted.attendee_client_ids, vec![bob.id]);
+    }
+
+    #[tokio::test]
+    async fn overlapping_reservation_conflicts() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+
+        repository
+            .create_reservation(&new_reservation(alice.id, 10, 12), None)
+            .await
+            .unwrap();
+
+        let err = repository
+            .create_reservation(&new_reservation(bob.id, 11, 13), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ReservationConflict));
+
+        // Back to back reservations don't overlap
+        repository
+            .create_reservation(&new_reservation(bob.id, 12, 13), None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn lapsed_holds_stop_blocking_before_they_are_swept() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+
+        let hold = repository
+            .create_reservation(
+                &NewReservation {
+                    hold_expires_at: Some(Utc::now() - Duration::minutes(1)),
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        repository
+            .create_reservation(&new_reservation(bob.id, 10, 11), None)
+            .await
+            .unwrap();
+
+        // Booking over it leaves the hold to the sweep, but it can't be extended any more
+        let hold = repository.get_reservation(hold.id).await.unwrap();
+        assert_eq!(hold.status, ReservationStatus::Pending);
+        let err = repository
+            .extend_reservation(hold.id, at(12), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::HoldExpired(id) if id == hold.id));
+    }
+
+    #[tokio::test]
+    async fn confirming_and_cancelling_are_recorded_as_events() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+
+        let hold = repository
+            .create_reservation(
+                &NewReservation {
+                    hold_expires_at: Some(Utc::now() + Duration::minutes(10)),
+                    ..new_reservation(alice.id, 10, 11)
+                },
+                Some("front-desk"),
+            )
+            .await
+            .unwrap();
+        let confirmed = repository
+            .confirm_reservation(hold.id, Some("payments"))
+            .await
+            .unwrap();
+        repository
+            .cancel_reservation(
+                hold.id,
+                None,
+                confirmed.version,
+                false,
+                None,
+                Some("front-desk"),
+            )
+            .await
+            .unwrap();
+
+        let events: Vec<_> = repository
+            .list_reservation_events(hold.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| (event.old_status, event.new_status, event.actor))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    None,
+                    ReservationStatus::Pending,
+                    Some("front-desk".to_string())
+                ),
+                (
+                    Some(ReservationStatus::Pending),
+                    ReservationStatus::Confirmed,
+                    Some("payments".to_string())
+                ),
+                (
+                    Some(ReservationStatus::Confirmed),
+                    ReservationStatus::Cancelled,
+                    Some("front-desk".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn slots_within_a_blackout_are_not_available() {
+        let (_db, repository) = setup().await;
+        repository
+            .add_blackout(None, at(12), at(14), "Public holiday")
+            .await
+            .unwrap();
+        let slots = [
+            TimeSlot {
+                start_time: at(10),
+                end_time: at(11),
+            },
+            TimeSlot {
+                start_time: at(11),
+                end_time: at(13),
+            },
+        ];
+
+        let conflicts = repository
+            .find_slot_conflicts_batch(DEFAULT_RESOURCE_ID, &slots)
+            .await
+            .unwrap();
+        assert!(conflicts[0].is_empty());
+        assert!(conflicts[1].reservation_ids.is_empty());
+        assert_eq!(
+            conflicts[1].unavailable_reason.as_deref(),
+            Some("Slot falls within a blackout: Public holiday")
+        );
+
+        assert!(repository
+            .is_slot_available(DEFAULT_RESOURCE_ID, at(10), at(11))
+            .await
+            .unwrap());
+        assert!(!repository
+            .is_slot_available(DEFAULT_RESOURCE_ID, at(13), at(15))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn slots_on_a_deactivated_resource_are_not_available() {
+        let (_db, repository) = setup().await;
+        let resource = repository.create_resource("Room 2", 1).await.unwrap();
+        assert!(repository
+            .is_slot_available(resource.id, at(10), at(11))
+            .await
+            .unwrap());
+
+        repository.deactivate_resource(resource.id).await.unwrap();
+
+        let conflicts = repository
+            .find_slot_conflicts(resource.id, at(10), at(11))
+            .await
+            .unwrap();
+        assert!(conflicts.reservation_ids.is_empty());
+        assert!(conflicts.unavailable_reason.is_some());
+    }
+
+    #[tokio::test]
+    async fn cancel_reservation_frees_the_slot() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+
+        repository
+            .cancel_reservation(
+                reservation.id,
+                Some(alice.id),
+                reservation.version,
+                false,
+                Some("plans changed"),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let cancelled = repository.get_reservation(reservation.id).await.unwrap();
+        assert_eq!(cancelled.status, ReservationStatus::Cancelled);
+        assert_eq!(
+            cancelled.cancellation_reason.as_deref(),
+            Some("plans changed")
+        );
+
+        repository
+            .create_reservation(&new_reservation(bob.id, 10, 11), None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reinstating_a_reservation_whose_slot_was_retaken_conflicts() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        repository
+            .cancel_reservation(reservation.id, None, reservation.version, false, None, None)
+            .await
+            .unwrap();
+        repository
+            .create_reservation(&new_reservation(bob.id, 10, 11), None)
+            .await
+            .unwrap();
+
+        let err = repository
+            .reinstate_reservation(reservation.id, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ReservationConflict));
+
+        let unchanged = repository.get_reservation(reservation.id).await.unwrap();
+        assert_eq!(unchanged.status, ReservationStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn reinstating_succeeds_once_the_retaken_slot_is_freed_again() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        repository
+            .cancel_reservation(reservation.id, None, reservation.version, false, None, None)
+            .await
+            .unwrap();
+        let retaken = repository
+            .create_reservation(&new_reservation(bob.id, 10, 11), None)
+            .await
+            .unwrap();
+        repository
+            .cancel_reservation(retaken.id, None, retaken.version, false, None, None)
+            .await
+            .unwrap();
+
+        let reinstated = repository
+            .reinstate_reservation(reservation.id, None)
+            .await
+            .unwrap();
+        assert_eq!(reinstated.status, ReservationStatus::Confirmed);
+        assert_eq!(reinstated.cancellation_reason, None);
+    }
+
+    #[tokio::test]
+    async fn extending_into_a_taken_slot_conflicts() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        repository
+            .create_reservation(&new_reservation(bob.id, 12, 13), None)
+            .await
+            .unwrap();
+
+        let err = repository
+            .extend_reservation(reservation.id, at(13), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ReservationConflict));
+        let unchanged = repository.get_reservation(reservation.id).await.unwrap();
+        assert_eq!(unchanged.end_time, at(11));
+
+        // Up to the next reservation is free, and the extension is recorded
+        let extended = repository
+            .extend_reservation(reservation.id, at(12), Some("front-desk"))
+            .await
+            .unwrap();
+        assert_eq!(extended.end_time, at(12));
+        let events = repository
+            .list_reservation_events(reservation.id)
+            .await
+            .unwrap();
+        let last = events.last().unwrap();
+        assert_eq!(last.old_status, Some(ReservationStatus::Confirmed));
+        assert_eq!(last.new_status, ReservationStatus::Confirmed);
+        assert_eq!(last.actor.as_deref(), Some("front-desk"));
+    }
+
+    #[tokio::test]
+    async fn extending_a_cancelled_reservation_is_refused() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+        repository
+            .cancel_reservation(reservation.id, None, reservation.version, false, None, None)
+            .await
+            .unwrap();
+
+        let err = repository
+            .extend_reservation(reservation.id, at(12), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ReservationCancelled(id) if id == reservation.id));
+    }
+
+    #[tokio::test]
+    async fn cancelling_an_already_cancelled_reservation_changes_nothing() {
+        let (_db, repository) = setup().await;
+        let client = create_client(&repository, "alice@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(client.id, 10, 11), None)
+            .await
+            .unwrap();
+        repository
+            .cancel_reservation(reservation.id, None, 1, false, Some("first"), None)
+            .await
+            .unwrap();
+        let cancelled = repository.get_reservation(reservation.id).await.unwrap();
+
+        repository
+            .cancel_reservation(
+                reservation.id,
+                None,
+                cancelled.version,
+                false,
+                Some("second"),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let unchanged = repository.get_reservation(reservation.id).await.unwrap();
+        assert_eq!(unchanged.status, ReservationStatus::Cancelled);
+        assert_eq!(unchanged.cancellation_reason.as_deref(), Some("first"));
+        assert_eq!(unchanged.version, cancelled.version);
+        assert_eq!(unchanged.updated_at, cancelled.updated_at);
+
+        let events = repository
+            .list_reservation_events(reservation.id)
+            .await
+            .unwrap();
+        let cancellations = events
+            .iter()
+            .filter(|event| event.new_status == ReservationStatus::Cancelled)
+            .count();
+        assert_eq!(cancellations, 1);
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_missing_reservation_is_not_found() {
+        let (_db, repository) = setup().await;
+        let id = Uuid::new_v4();
+
+        let err = repository
+            .cancel_reservation(id, None, 1, false, None, None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RepositoryError::ReservationNotFound(missing) if missing == id));
+    }
+
+    #[tokio::test]
+    async fn cancel_reservation_checks_owner_and_version() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(alice.id, 10, 11), None)
+            .await
+            .unwrap();
+
+        let err = repository
+            .cancel_reservation(reservation.id, Some(bob.id), 1, false, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ReservationNotOwned { .. }));
+
+        let err = repository
+            .cancel_reservation(reservation.id, Some(alice.id), 2, false, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::VersionConflict(_)));
+
+        let unchanged = repository.get_reservation(reservation.id).await.unwrap();
+        assert_eq!(unchanged.status, ReservationStatus::Confirmed);
+    }
+
+    #[tokio::test]
+    async fn updated_at_changes_on_cancel_but_not_on_reads() {
+        let (_db, repository) = setup().await;
+        let client = create_client(&repository, "alice@example.com").await;
+        let created = repository
+            .create_reservation(&new_reservation(client.id, 10, 11), None)
+            .await
+            .unwrap();
+        assert_eq!(created.updated_at, created.created_at);
+
+        let filter = ReservationFilter {
+            client_id: Some(client.id),
+            ..Default::default()
+        };
+        repository
+            .list_reservations(&filter, 10, None)
+            .await
+            .unwrap();
+        let read = repository.get_reservation(created.id).await.unwrap();
+        assert_eq!(read.updated_at, created.updated_at);
+
+        repository
+            .cancel_reservation(created.id, None, created.version, false, None, None)
+            .await
+            .unwrap();
+
+        let cancelled = repository.get_reservation(created.id).await.unwrap();
+        assert!(cancelled.updated_at > created.updated_at);
+        assert!(cancelled.updated_at >= cancelled.created_at);
+        assert_eq!(cancelled.created_at, created.created_at);
+    }
+
+    #[tokio::test]
+    async fn update_notes_refuses_cancelled_and_missing_reservations() {
+        let (_db, repository) = setup().await;
+        let client = create_client(&repository, "alice@example.com").await;
+        let reservation = repository
+            .create_reservation(&new_reservation(client.id, 10, 11), None)
+            .await
+            .unwrap();
+
+        let updated = repository
+            .update_notes(reservation.id, Some("window seat"))
+            .await
+            .unwrap();
+        assert_eq!(updated.notes.as_deref(), Some("window seat"));
+        assert_eq!(updated.version, reservation.version + 1);
+
+        repository
+            .cancel_reservation(reservation.id, None, updated.version, false, None, None)
+            .await
+            .unwrap();
+        let err = repository
+            .update_notes(reservation.id, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ReservationCancelled(_)));
+
+        let err = repository
+            .update_notes(Uuid::new_v4(), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::ReservationNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn list_reservations_filters_and_pages() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        for hour in [9, 11, 13] {
+            repository
+                .create_reservation(&new_reservation(alice.id, hour, hour + 1), None)
+                .await
+                .unwrap();
+        }
+        repository
+            .create_reservation(&new_reservation(bob.id, 15, 16), None)
+            .await
+            .unwrap();
+
+        let filter = ReservationFilter {
+            client_id: Some(alice.id),
+            ..Default::default()
+        };
+        let first = repository
+            .list_reservations(&filter, 2, None)
+            .await
+            .unwrap();
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.items[0].start_time, at(9));
+        assert_eq!(first.items[1].start_time, at(11));
+        assert!(first.next_cursor.is_some());
+
+        let second = repository
+            .list_reservations(&filter, 2, first.next_cursor)
+            .await
+            .unwrap();
+        assert_eq!(second.items.len(), 1);
+        assert_eq!(second.items[0].start_time, at(13));
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn client_reservations_straddling_the_range_bounds_are_returned() {
+        let (_db, repository) = setup().await;
+        let alice = create_client(&repository, "alice@example.com").await;
+        let bob = create_client(&repository, "bob@example.com").await;
+        for hour in [9, 11, 13, 15] {
+            repository
+                .create_reservation(&new_reservation(alice.id, hour, hour + 1), None)
+                .await
+                .unwrap();
+        }
+        repository
+            .create_reservation(&new_reservation(bob.id, 12, 13), None)
+            .await
+            .unwrap();
+
+        // The 11:00 and 15:00 reservations only partly overlap the range, 09:00 ends before it
+        let filter = ReservationFilter {
+            start_time: Some(at(11) + Duration::minutes(30)),
+            end_time: Some(at(15) + Duration::minutes(30)),
+            ..Default::default()
+        };
+        let page = repository
+            .get_client_reservations(alice.id, &filter, 10, None)
+            .await
+            .unwrap();
+        let starts: Vec<_> = page.items.iter().map(|res| res.start_time).collect();
+        assert_eq!(starts, vec![at(11), at(13), at(15)]);
     }
 }
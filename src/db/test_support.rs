@@ -0,0 +1,75 @@
+//! Throwaway databases for the tests that need a real Postgres
+
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, PgConnection, PgPool};
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::ContainerAsync;
+use uuid::Uuid;
+
+/// Server to create the test databases on instead of starting a container, e.g.
+/// `postgres://postgres@localhost:5432/postgres`
+const TEST_DATABASE_URL_VAR: &str = "TEST_DATABASE_URL";
+
+/// A freshly migrated database of its own for a single test
+///
+/// A database started in a container goes away with it when this is dropped, one created on
+/// the `TEST_DATABASE_URL` server is left behind for inspection.
+pub struct TestDatabase {
+    pub pool: PgPool,
+    _container: Option<ContainerAsync<Postgres>>,
+}
+
+impl TestDatabase {
+    pub async fn new() -> Self {
+        let (server_url, container) = match std::env::var(TEST_DATABASE_URL_VAR) {
+            Ok(url) => (url, None),
+            Err(_) => {
+                let container = Postgres::default()
+                    .start()
+                    .await
+                    .expect("Failed to start Postgres container");
+                let host = container.get_host().await.expect("Container has no host");
+                let port = container
+                    .get_host_port_ipv4(5432)
+                    .await
+                    .expect("Container doesn't expose Postgres");
+                (
+                    format!("postgres://postgres:postgres@{host}:{port}/postgres"),
+                    Some(container),
+                )
+            }
+        };
+
+        // Each test gets its own database so they can run in parallel on the same server
+        let name = format!("reservations_test_{}", Uuid::new_v4().simple());
+        let mut conn = PgConnection::connect(&server_url)
+            .await
+            .expect("Failed to connect to test server");
+        sqlx::query(&format!("CREATE DATABASE {name}"))
+            .execute(&mut conn)
+            .await
+            .expect("Failed to create test database");
+        let _ = conn.close().await;
+
+        let options = server_url
+            .parse::<PgConnectOptions>()
+            .expect("Invalid test database URL")
+            .database(&name);
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await
+            .expect("Failed to connect to test database");
+
+        sqlx::migrate!("./db")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        Self {
+            pool,
+            _container: container,
+        }
+    }
+}
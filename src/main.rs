@@ -1,20 +1,45 @@
 use anyhow::Result;
 use dotenv::dotenv;
+use sqlx::PgPool;
+use std::collections::HashSet;
 use std::env;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
 use tonic::transport::Server;
+use tonic::Status;
+use tonic_health::server::HealthReporter;
+use tower::load_shed::error::Overloaded;
+use tower::timeout::error::Elapsed;
+use tower::{BoxError, ServiceBuilder};
 
 pub mod proto {
     tonic::include_proto!("reservations");
+
+    /// Encoded descriptors of the service, served for reflection
+    pub const FILE_DESCRIPTOR_SET: &[u8] =
+        tonic::include_file_descriptor_set!("reservations_descriptor");
 }
 
 pub mod db;
+pub mod metrics;
+pub mod middleware;
 pub mod service;
 
-use db::ReservationRepository;
+use db::{ReservationChange, ReservationRepository, ReservationStatus};
+use metrics::{Metrics, MetricsLayer};
+use middleware::{ApiKeyInterceptor, RequestLogLayer};
 use proto::reservation_service_server::ReservationServiceServer;
-use service::ReservationServiceImpl;
+use service::{BusinessHours, ReservationServiceImpl, ServiceConfig};
+
+/// Longest delay between sweeps while the database keeps failing
+const MAX_SWEEP_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Delay before listening for reservation changes again after the listener failed, doubling
+/// up to `MAX_CHANGE_FEED_BACKOFF` while it keeps failing
+const CHANGE_FEED_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_CHANGE_FEED_BACKOFF: Duration = Duration::from_secs(60);
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -24,19 +49,137 @@ async fn main() -> Result<()> {
     // Setup logging
     tracing_subscriber::fmt::init();
 
-    // Get database URL from environment
-    let database_url =
-        env::var("DATABASE_URL").expect("DATABASE_URL environment variable must be set");
+    // Get database URL from environment, or assemble it from its parts
+    let database_url = match env::var("DATABASE_URL") {
+        Ok(url) => url,
+        Err(_) => database_url_from_parts()?,
+    };
 
     // Get server address from environment or use default
     let addr = env::var("SERVER_ADDR")
         .unwrap_or_else(|_| "0.0.0.0:50051".to_string())
         .parse::<SocketAddr>()?;
 
-    tracing::info!("Connecting to database...");
+    // Get the maximum reservation length in minutes, zero means unlimited
+    let max_reservation_minutes = env::var("MAX_RESERVATION_MINUTES")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get the minimum reservation length in minutes, zero means no minimum
+    let min_reservation_minutes = env::var("MIN_RESERVATION_MINUTES")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get the start time granularity in minutes, zero means any start time is accepted
+    let start_alignment_minutes = env::var("RESERVATION_START_ALIGNMENT")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get how long before the start reservations must be made, zero means no lead time
+    let min_lead_time_minutes = env::var("MIN_LEAD_TIME_MINUTES")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get how many days ahead reservations can be made, zero means no limit
+    let booking_horizon_days = env::var("BOOKING_HORIZON_DAYS")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get how many days ahead reservations can start, zero means no limit
+    let max_advance_days = env::var("MAX_ADVANCE_DAYS")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get how long before the start reservations stop being cancellable, zero means never
+    let cancellation_cutoff_minutes = env::var("CANCELLATION_CUTOFF_MINUTES")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get the opening hours during which slots are offered, on the local clock of each
+    // request's time zone, defaults to always open
+    let business_open_hour = env::var("BUSINESS_OPEN_HOUR")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<u32>()?;
+    let business_close_hour = env::var("BUSINESS_CLOSE_HOUR")
+        .unwrap_or_else(|_| "24".to_string())
+        .parse::<u32>()?;
+    if business_open_hour >= business_close_hour || business_close_hour > 24 {
+        anyhow::bail!(
+            "BUSINESS_OPEN_HOUR must be before BUSINESS_CLOSE_HOUR, which can be at most 24"
+        );
+    }
+    let business_days = env::var("BUSINESS_DAYS")
+        .unwrap_or_else(|_| "mon,tue,wed,thu,fri,sat,sun".to_string())
+        .split(',')
+        .map(|day| {
+            day.trim()
+                .parse::<chrono::Weekday>()
+                .map_err(|_| anyhow::anyhow!("Invalid day in BUSINESS_DAYS: {}", day))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Get the time zone the availability rules are written in, and whether bookings must
+    // follow them as well as the offered slots
+    let availability_time_zone = env::var("AVAILABILITY_TIME_ZONE")
+        .unwrap_or_else(|_| "UTC".to_string())
+        .parse::<chrono_tz::Tz>()
+        .map_err(|_| anyhow::anyhow!("Invalid AVAILABILITY_TIME_ZONE"))?;
+    let enforce_availability_rules = env::var("ENFORCE_AVAILABILITY_RULES")
+        .unwrap_or_else(|_| "false".to_string())
+        .parse::<bool>()?;
+
+    // Get the longest notes a reservation may carry in characters, zero means unlimited
+    let max_notes_length = env::var("MAX_NOTES_LENGTH")
+        .unwrap_or_else(|_| "1000".to_string())
+        .parse::<usize>()?;
+
+    // Get the token that unlocks admin-only RPCs, they are disabled when it isn't set
+    let admin_token = env::var("ADMIN_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+
+    let config = ServiceConfig {
+        max_reservation_duration: positive_minutes(max_reservation_minutes),
+        min_reservation_duration: positive_minutes(min_reservation_minutes),
+        start_alignment: positive_minutes(start_alignment_minutes),
+        min_lead_time: positive_minutes(min_lead_time_minutes),
+        booking_horizon: (booking_horizon_days > 0)
+            .then(|| chrono::Duration::days(booking_horizon_days)),
+        max_advance: (max_advance_days > 0).then(|| chrono::Duration::days(max_advance_days)),
+        cancellation_cutoff: positive_minutes(cancellation_cutoff_minutes),
+        business_hours: Some(BusinessHours {
+            open_hour: business_open_hour,
+            close_hour: business_close_hour,
+            open_days: business_days,
+        }),
+        availability_time_zone: Some(availability_time_zone),
+        enforce_availability_rules,
+        admin_token,
+        max_notes_length: (max_notes_length > 0).then_some(max_notes_length),
+    };
+
+    // Get database pool settings from environment or use defaults
+    let max_connections = env::var("DB_MAX_CONNECTIONS")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse::<u32>()?;
+    let min_connections = env::var("DB_MIN_CONNECTIONS")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<u32>()?;
+    let acquire_timeout_secs = env::var("DB_ACQUIRE_TIMEOUT_SECS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse::<u64>()?;
+
+    tracing::info!(
+        max_connections,
+        min_connections,
+        acquire_timeout_secs,
+        "Connecting to database..."
+    );
     // Create database connection pool
     let pool = sqlx::postgres::PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(max_connections)
+        .min_connections(min_connections)
+        .acquire_timeout(Duration::from_secs(acquire_timeout_secs))
         .connect(&database_url)
         .await?;
 
@@ -44,18 +187,371 @@ async fn main() -> Result<()> {
     tracing::info!("Running database migrations...");
     sqlx::migrate!("./db").run(&pool).await?;
 
+    // Get the database health check interval from environment or use default
+    let health_check_interval_secs = env::var("HEALTH_CHECK_INTERVAL_SECS")
+        .unwrap_or_else(|_| "10".to_string())
+        .parse::<u64>()?;
+
+    // Report the service as serving now that the database is reachable and migrated
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_serving::<ReservationServiceServer<ReservationServiceImpl>>()
+        .await;
+    tokio::spawn(monitor_database_health(
+        pool.clone(),
+        health_reporter,
+        Duration::from_secs(health_check_interval_secs),
+    ));
+
+    // Get the interval between sweeps of lapsed holds from environment or use default
+    let hold_sweep_interval_secs = env::var("HOLD_SWEEP_INTERVAL_SECS")
+        .unwrap_or_else(|_| "60".to_string())
+        .parse::<u64>()?;
+
+    // Get the interval between sweeps completing ended reservations or use default
+    let completion_sweep_interval_secs = env::var("COMPLETION_SWEEP_INTERVAL_SECS")
+        .unwrap_or_else(|_| "300".to_string())
+        .parse::<u64>()?;
+
+    // Get how many hours an idempotency key keeps returning the reservation it created
+    let idempotency_key_ttl_hours = env::var("IDEMPOTENCY_KEY_TTL_HOURS")
+        .unwrap_or_else(|_| "24".to_string())
+        .parse::<i32>()?;
+
+    // Get how many active reservations a client may hold by default, zero means unlimited
+    let reservation_quota = env::var("MAX_ACTIVE_RESERVATIONS_PER_CLIENT")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<i64>()?;
+
+    // Get which statuses take up a slot, drop pending to let unconfirmed holds be overbooked
+    let blocking_statuses = env::var("BLOCKING_STATUSES")
+        .unwrap_or_else(|_| "confirmed,pending".to_string())
+        .split(',')
+        .map(|status| {
+            status
+                .trim()
+                .parse::<ReservationStatus>()
+                .map_err(|_| anyhow::anyhow!("Invalid status in BLOCKING_STATUSES: {}", status))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
     // Create repository
-    let repository = Arc::new(ReservationRepository::new(pool));
+    let repository = Arc::new(
+        ReservationRepository::new(pool)
+            .with_idempotency_key_ttl_hours(idempotency_key_ttl_hours)
+            .with_reservation_quota((reservation_quota > 0).then_some(reservation_quota))
+            .with_blocking_statuses(blocking_statuses),
+    );
+
+    // Start the background sweeper for lapsed holds
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+    let sweeper = tokio::spawn(sweep_expired_holds(
+        repository.clone(),
+        Duration::from_secs(hold_sweep_interval_secs),
+        shutdown_rx.clone(),
+    ));
+
+    // Start the background sweeper completing confirmed reservations that have ended
+    let completion_sweeper = tokio::spawn(sweep_ended_reservations(
+        repository.clone(),
+        Duration::from_secs(completion_sweep_interval_secs),
+        shutdown_rx.clone(),
+    ));
+
+    // Get the per-request deadline and the limit on requests handled at once
+    let request_timeout_ms = env::var("REQUEST_TIMEOUT_MS")
+        .unwrap_or_else(|_| "30000".to_string())
+        .parse::<u64>()?;
+    let max_concurrent_requests = env::var("MAX_CONCURRENT_REQUESTS")
+        .unwrap_or_else(|_| "256".to_string())
+        .parse::<usize>()?;
+
+    // Get the address the Prometheus metrics are served on over HTTP
+    let metrics_addr = env::var("METRICS_ADDR")
+        .unwrap_or_else(|_| "0.0.0.0:9090".to_string())
+        .parse::<SocketAddr>()?;
+
+    let metrics = Arc::new(Metrics::new()?);
+    let grpc_methods = metrics::grpc_methods(&[
+        proto::FILE_DESCRIPTOR_SET,
+        tonic_health::pb::FILE_DESCRIPTOR_SET,
+        tonic_reflection::pb::FILE_DESCRIPTOR_SET,
+    ])?;
+    let mut metrics_shutdown = shutdown_rx.clone();
+    tracing::info!("Starting metrics server on {}", metrics_addr);
+    let metrics_server = tokio::spawn(metrics::serve(metrics_addr, metrics.clone(), async move {
+        let _ = metrics_shutdown.changed().await;
+    }));
+
+    // Requests beyond the limit are rejected straight away instead of queueing for the pool,
+    // metrics sit outside the limits so that rejected requests are counted too
+    let layer = ServiceBuilder::new()
+        .layer(RequestLogLayer)
+        .layer(MetricsLayer::new(metrics, grpc_methods))
+        .map_err(layer_error_to_status)
+        .load_shed()
+        .concurrency_limit(max_concurrent_requests)
+        .timeout(Duration::from_millis(request_timeout_ms))
+        .into_inner();
+
+    // Get the API keys callers must present, authentication is off when none are set
+    let api_keys = env::var("API_KEY")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect::<HashSet<_>>();
+    if api_keys.is_empty() {
+        tracing::warn!("API_KEY is not set, accepting unauthenticated requests");
+    }
 
     // Create gRPC service
-    let reservation_service = ReservationServiceImpl::new(repository);
+    let reservation_service = ReservationServiceImpl::new(repository.clone(), config);
+
+    // Feed committed reservation changes from the database to WatchReservations subscribers
+    let relay = tokio::spawn(relay_reservation_changes(
+        repository,
+        reservation_service.change_feed(),
+        shutdown_rx,
+    ));
+
+    // Let tools such as grpcurl discover the service without a copy of the proto file
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+        .build()?;
 
     // Create gRPC server
     tracing::info!("Starting gRPC server on {}", addr);
-    Server::builder()
-        .add_service(ReservationServiceServer::new(reservation_service))
-        .serve(addr)
-        .await?;
+    let result = Server::builder()
+        .layer(layer)
+        .add_service(health_service)
+        .add_service(reflection_service)
+        .add_service(ReservationServiceServer::with_interceptor(
+            reservation_service,
+            ApiKeyInterceptor::new(api_keys),
+        ))
+        .serve_with_shutdown(addr, async {
+            tokio::signal::ctrl_c().await.ok();
+            tracing::info!("Shutting down gRPC server...");
+        })
+        .await;
+
+    // Stop the background tasks once the server is no longer accepting requests
+    let _ = shutdown_tx.send(());
+    let _ = sweeper.await;
+    let _ = completion_sweeper.await;
+    let _ = relay.await;
+    match metrics_server.await {
+        Ok(Err(e)) => tracing::warn!("Metrics server failed: {}", e),
+        Err(e) => tracing::warn!("Metrics server task panicked: {}", e),
+        Ok(Ok(())) => {}
+    }
+
+    result?;
 
     Ok(())
 }
+
+/// Build a connection string from `DB_HOST`, `DB_PORT`, `DB_USER`, `DB_PASSWORD` and
+/// `DB_NAME`, for deployments that inject the parts separately
+///
+/// The port defaults to 5432 and the password may be left out.
+fn database_url_from_parts() -> Result<String> {
+    let required = |name: &str| {
+        env::var(name).map_err(|_| {
+            anyhow::anyhow!(
+                "Either DATABASE_URL or {} environment variable must be set",
+                name
+            )
+        })
+    };
+
+    let host = required("DB_HOST")?;
+    let user = required("DB_USER")?;
+    let name = required("DB_NAME")?;
+    let port = env::var("DB_PORT")
+        .unwrap_or_else(|_| "5432".to_string())
+        .parse::<u16>()?;
+
+    let credentials = match env::var("DB_PASSWORD") {
+        Ok(password) => format!("{}:{}", percent_encode(&user), percent_encode(&password)),
+        Err(_) => percent_encode(&user),
+    };
+
+    Ok(format!(
+        "postgres://{}@{}:{}/{}",
+        credentials,
+        host,
+        port,
+        percent_encode(&name)
+    ))
+}
+
+/// Percent-encode everything but unreserved characters so a value can't break URL parsing
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Convert a minute count from the environment into a duration, non-positive values disable it
+fn positive_minutes(minutes: i64) -> Option<chrono::Duration> {
+    (minutes > 0).then(|| chrono::Duration::minutes(minutes))
+}
+
+/// Turn errors raised by the timeout and load shedding layers into gRPC statuses
+fn layer_error_to_status(err: BoxError) -> BoxError {
+    if err.is::<Elapsed>() {
+        Status::deadline_exceeded("Request timed out").into()
+    } else if err.is::<Overloaded>() {
+        Status::resource_exhausted("Too many concurrent requests").into()
+    } else {
+        err
+    }
+}
+
+/// Periodically ping the database, marking the service as not serving while it is unreachable
+async fn monitor_database_health(pool: PgPool, mut reporter: HealthReporter, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut healthy = true;
+
+    loop {
+        ticker.tick().await;
+
+        match sqlx::query("SELECT 1").execute(&pool).await {
+            Ok(_) if !healthy => {
+                tracing::info!("Database health check recovered");
+                reporter
+                    .set_serving::<ReservationServiceServer<ReservationServiceImpl>>()
+                    .await;
+                healthy = true;
+            }
+            Err(e) if healthy => {
+                tracing::warn!("Database health check failed: {}", e);
+                reporter
+                    .set_not_serving::<ReservationServiceServer<ReservationServiceImpl>>()
+                    .await;
+                healthy = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Periodically expire lapsed holds until shutdown, backing off while the database is failing
+async fn sweep_expired_holds(
+    repository: Arc<ReservationRepository>,
+    interval: Duration,
+    mut shutdown: watch::Receiver<()>,
+) {
+    let mut delay = interval;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.changed() => break,
+        }
+
+        match repository.expire_stale_holds(chrono::Utc::now()).await {
+            Ok(count) => {
+                if count > 0 {
+                    tracing::info!(count, "Expired stale holds");
+                }
+                delay = interval;
+            }
+            Err(e) => {
+                delay = (delay * 2).min(MAX_SWEEP_BACKOFF);
+                tracing::warn!(
+                    "Failed to expire stale holds, retrying in {:?}: {}",
+                    delay,
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Periodically mark confirmed reservations that have ended as completed until shutdown,
+/// backing off while the database is failing
+async fn sweep_ended_reservations(
+    repository: Arc<ReservationRepository>,
+    interval: Duration,
+    mut shutdown: watch::Receiver<()>,
+) {
+    let mut delay = interval;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.changed() => break,
+        }
+
+        match repository
+            .complete_ended_reservations(chrono::Utc::now())
+            .await
+        {
+            Ok(count) => {
+                if count > 0 {
+                    tracing::info!(count, "Completed ended reservations");
+                }
+                delay = interval;
+            }
+            Err(e) => {
+                delay = (delay * 2).min(MAX_SWEEP_BACKOFF);
+                tracing::warn!(
+                    "Failed to complete ended reservations, retrying in {:?}: {}",
+                    delay,
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Publish reservation changes announced by the database until shutdown, listening again
+/// with backoff whenever the listener fails
+async fn relay_reservation_changes(
+    repository: Arc<ReservationRepository>,
+    changes: broadcast::Sender<ReservationChange>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    let mut delay = CHANGE_FEED_RETRY_DELAY;
+
+    loop {
+        let err = match repository.listen_for_changes().await {
+            Ok(mut listener) => loop {
+                tokio::select! {
+                    change = listener.recv() => match change {
+                        Ok(change) => {
+                            delay = CHANGE_FEED_RETRY_DELAY;
+                            // Sending only fails while nobody is watching
+                            let _ = changes.send(change);
+                        }
+                        Err(e) => break e,
+                    },
+                    _ = shutdown.changed() => return,
+                }
+            },
+            Err(e) => e,
+        };
+
+        tracing::warn!(
+            "Reservation change listener failed, retrying in {:?}: {}",
+            delay,
+            err
+        );
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.changed() => return,
+        }
+        delay = (delay * 2).min(MAX_CHANGE_FEED_BACKOFF);
+    }
+}
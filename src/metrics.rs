@@ -0,0 +1,283 @@
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, StatusCode};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::codegen::http::{Request, Response};
+use tonic::codegen::BoxFuture;
+use tonic::Status;
+use tower::{BoxError, Layer, Service};
+
+/// Method label recorded for request paths that aren't a known RPC
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// RPC metrics exported in the Prometheus text format
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    latency: HistogramVec,
+    errors: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let requests = IntCounterVec::new(
+            Opts::new("grpc_requests_total", "RPCs handled, by method"),
+            &["method"],
+        )?;
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "grpc_request_duration_seconds",
+                "Time until the response headers were sent, by method",
+            ),
+            &["method"],
+        )?;
+        let errors = IntCounterVec::new(
+            Opts::new(
+                "grpc_errors_total",
+                "RPCs that failed, by method and gRPC status code",
+            ),
+            &["method", "code"],
+        )?;
+
+        let registry = Registry::new();
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        registry.register(Box::new(errors.clone()))?;
+
+        Ok(Self {
+            registry,
+            requests,
+            latency,
+            errors,
+        })
+    }
+
+    fn record(&self, method: &str, seconds: f64, code: &str) {
+        self.requests.with_label_values(&[method]).inc();
+        self.latency.with_label_values(&[method]).observe(seconds);
+        if code != "0" {
+            self.errors.with_label_values(&[method, code]).inc();
+        }
+    }
+
+    /// Render every metric in the Prometheus text format
+    fn render(&self) -> Result<Vec<u8>, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Collect the `/<package>.<service>/<method>` paths of every RPC in the encoded file
+/// descriptor sets
+pub fn grpc_methods(descriptor_sets: &[&[u8]]) -> Result<HashSet<String>, prost::DecodeError> {
+    let mut methods = HashSet::new();
+
+    for encoded in descriptor_sets {
+        for file in FileDescriptorSet::decode(*encoded)?.file {
+            let prefix = match file.package() {
+                "" => String::new(),
+                package => format!("{}.", package),
+            };
+            for service in &file.service {
+                for method in &service.method {
+                    methods.insert(format!("/{}{}/{}", prefix, service.name(), method.name()));
+                }
+            }
+        }
+    }
+
+    Ok(methods)
+}
+
+/// Serve the metrics over HTTP at `/metrics` until `shutdown` completes
+pub async fn serve(
+    addr: SocketAddr,
+    metrics: Arc<Metrics>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: hyper::Request<Body>| {
+                let metrics = metrics.clone();
+                async move { Ok::<_, Infallible>(metrics_response(&metrics, &req)) }
+            }))
+        }
+    });
+
+    hyper::Server::bind(&addr)
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown)
+        .await
+}
+
+fn metrics_response(metrics: &Metrics, req: &hyper::Request<Body>) -> hyper::Response<Body> {
+    let mut response = hyper::Response::new(Body::empty());
+
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+
+    match metrics.render() {
+        Ok(body) => {
+            response.headers_mut().insert(
+                hyper::header::CONTENT_TYPE,
+                hyper::header::HeaderValue::from_static("text/plain; version=0.0.4"),
+            );
+            *response.body_mut() = Body::from(body);
+        }
+        Err(e) => {
+            tracing::error!("Failed to render metrics: {}", e);
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    }
+
+    response
+}
+
+/// Layer that counts every RPC and records its latency and status code
+///
+/// Failures are read from the `grpc-status` response header, which tonic sets for errors
+/// sent trailers-only, or from the status carried by an error raised by an inner layer.
+/// Errors a streaming RPC reports after it started sending messages are not counted.
+///
+/// This runs before routing and authentication, so requests for any path other than one of
+/// `methods` are recorded under a single `unknown` method to keep the number of series
+/// bounded.
+#[derive(Clone)]
+pub struct MetricsLayer {
+    metrics: Arc<Metrics>,
+    methods: Arc<HashSet<String>>,
+}
+
+impl MetricsLayer {
+    pub fn new(metrics: Arc<Metrics>, methods: HashSet<String>) -> Self {
+        Self {
+            metrics,
+            methods: Arc::new(methods),
+        }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+            methods: self.methods.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: Arc<Metrics>,
+    methods: Arc<HashSet<String>>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for MetricsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error = BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let method = match req.uri().path() {
+            path if self.methods.contains(path) => path.to_string(),
+            _ => UNKNOWN_METHOD.to_string(),
+        };
+        let metrics = self.metrics.clone();
+        let start = Instant::now();
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let result = future.await;
+            let seconds = start.elapsed().as_secs_f64();
+
+            let code = match &result {
+                Ok(response) => response
+                    .headers()
+                    .get("grpc-status")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or("0")
+                    .to_string(),
+                Err(err) => err
+                    .downcast_ref::<Status>()
+                    .map(|status| (status.code() as i32).to_string())
+                    .unwrap_or_else(|| (tonic::Code::Unknown as i32).to_string()),
+            };
+            metrics.record(&method, seconds, &code);
+
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_types::{FileDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto};
+
+    fn descriptor_set(package: &str, service: &str, methods: &[&str]) -> Vec<u8> {
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                package: Some(package.to_string()),
+                service: vec![ServiceDescriptorProto {
+                    name: Some(service.to_string()),
+                    method: methods
+                        .iter()
+                        .map(|name| MethodDescriptorProto {
+                            name: Some(name.to_string()),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn grpc_methods_lists_the_paths_of_every_rpc() {
+        let reservations = descriptor_set(
+            "reservations",
+            "ReservationService",
+            &["CreateReservation", "CancelReservation"],
+        );
+        let health = descriptor_set("grpc.health.v1", "Health", &["Check"]);
+
+        let methods = grpc_methods(&[&reservations, &health]).unwrap();
+        assert_eq!(
+            methods,
+            HashSet::from([
+                "/reservations.ReservationService/CreateReservation".to_string(),
+                "/reservations.ReservationService/CancelReservation".to_string(),
+                "/grpc.health.v1.Health/Check".to_string(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::codegen::http::{HeaderValue, Request, Response};
+use tonic::codegen::BoxFuture;
+use tonic::service::Interceptor;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::Status;
+use tower::{Layer, Service};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Metadata key carrying the correlation ID of a request, in both directions
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Metadata key carrying the caller's API key, either bare or as `Bearer <key>`
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// Interceptor rejecting calls that don't carry one of the configured API keys
+///
+/// With no keys configured every call is let through, so local development works without one.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyInterceptor {
+    keys: Arc<HashSet<String>>,
+}
+
+impl ApiKeyInterceptor {
+    pub fn new(keys: HashSet<String>) -> Self {
+        Self {
+            keys: Arc::new(keys),
+        }
+    }
+
+    /// Returns true if `provided` matches a configured key, comparing in constant time
+    fn is_valid(&self, provided: &str) -> bool {
+        self.keys.iter().fold(false, |valid, key| {
+            valid | constant_time_eq(key.as_bytes(), provided.as_bytes())
+        })
+    }
+}
+
+impl Interceptor for ApiKeyInterceptor {
+    fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        if self.keys.is_empty() {
+            return Ok(request);
+        }
+
+        let provided = request
+            .metadata()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.strip_prefix("Bearer ").unwrap_or(value).trim());
+
+        match provided {
+            Some(key) if self.is_valid(key) => Ok(request),
+            Some(_) => Err(Status::unauthenticated("Invalid API key")),
+            None => Err(Status::unauthenticated("Missing API key")),
+        }
+    }
+}
+
+/// Compare two byte strings without exiting early, so timing doesn't reveal a matching prefix
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Layer that runs every RPC inside a span tagged with a correlation ID and logs its outcome
+///
+/// The ID is taken from the caller's `x-request-id` metadata when present, otherwise a fresh
+/// UUID is generated. Either way it is returned in the response metadata under the same key.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLogLayer;
+
+impl<S> Layer<S> for RequestLogLayer {
+    type Service = RequestLog<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestLog { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestLog<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RequestLog<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: std::fmt::Display,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let peer = req
+            .extensions()
+            .get::<TcpConnectInfo>()
+            .and_then(TcpConnectInfo::remote_addr)
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+
+        let span = tracing::info_span!(
+            "rpc",
+            request_id = %request_id,
+            method = %req.uri().path(),
+            peer = %peer,
+        );
+
+        let start = Instant::now();
+        let future = span.in_scope(|| self.inner.call(req));
+
+        Box::pin(
+            async move {
+                let result = future.await;
+                let latency_ms = start.elapsed().as_millis() as u64;
+
+                match result {
+                    Ok(mut response) => {
+                        // Errors are sent trailers-only, so a missing status in the headers
+                        // means the call succeeded or is a stream still in progress
+                        let grpc_status = response
+                            .headers()
+                            .get("grpc-status")
+                            .and_then(|value| value.to_str().ok())
+                            .unwrap_or("0")
+                            .to_string();
+                        tracing::info!(latency_ms, grpc_status = %grpc_status, "Request completed");
+
+                        if let Ok(value) = HeaderValue::from_str(&request_id) {
+                            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                        }
+
+                        Ok(response)
+                    }
+                    Err(err) => {
+                        tracing::warn!(latency_ms, "Request failed: {}", err);
+                        Err(err)
+                    }
+                }
+            }
+            .instrument(span),
+        )
+    }
+}
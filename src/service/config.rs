@@ -0,0 +1,113 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+/// Business rules enforced by the reservation service
+#[derive(Debug, Clone, Default)]
+pub struct ServiceConfig {
+    /// Longest reservation that may be booked, `None` means unlimited
+    pub max_reservation_duration: Option<Duration>,
+    /// Shortest reservation that may be booked, `None` means no minimum
+    pub min_reservation_duration: Option<Duration>,
+    /// Reservations must start on a multiple of this interval since midnight UTC
+    pub start_alignment: Option<Duration>,
+    /// Reservations must be made at least this long before they start, `None` means they
+    /// can be made up to the last moment
+    pub min_lead_time: Option<Duration>,
+    /// Reservations must end within this long from now, `None` means any time ahead
+    pub booking_horizon: Option<Duration>,
+    /// Reservations must start within this long from now, `None` means any time ahead
+    pub max_advance: Option<Duration>,
+    /// Reservations can't be cancelled once their start is closer than this, `None` means
+    /// they can be cancelled at any time
+    pub cancellation_cutoff: Option<Duration>,
+    /// Only slots within these hours are offered, `None` means any time of day
+    pub business_hours: Option<BusinessHours>,
+    /// Time zone whose local clock the availability rules are read on, `None` means UTC
+    pub availability_time_zone: Option<Tz>,
+    /// Reject reservations outside the availability rules unless an admin overrides it,
+    /// otherwise the rules only limit the slots offered
+    pub enforce_availability_rules: bool,
+    /// Token callers must send as `x-admin-token` metadata for admin-only RPCs, `None`
+    /// disables those RPCs
+    pub admin_token: Option<String>,
+    /// Longest notes a reservation may carry in characters, `None` means unlimited
+    pub max_notes_length: Option<usize>,
+}
+
+/// Opening hours that offered slots must fall within, on the wall clock of a time zone
+#[derive(Debug, Clone)]
+pub struct BusinessHours {
+    /// Hour of the day the business opens, from 0 to 23
+    pub open_hour: u32,
+    /// Hour of the day the business closes, from 1 to 24 where 24 means midnight
+    pub close_hour: u32,
+    /// Days of the week the business is open
+    pub open_days: Vec<Weekday>,
+}
+
+impl BusinessHours {
+    /// Returns true if the whole of `[start, end)` falls within opening hours on an open day,
+    /// with the hours and days read off the local clock in `tz`
+    ///
+    /// The comparison uses local wall-clock times, so a slot spanning a DST transition is
+    /// judged by when it starts and ends locally. A slot crossing a spring-forward gap is
+    /// offered as long as both ends are within opening hours, even though the gap itself
+    /// has no local times.
+    pub fn contains<Tz: TimeZone>(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        tz: &Tz,
+    ) -> bool {
+        let local_start = start.with_timezone(tz).naive_local();
+        let local_end = end.with_timezone(tz).naive_local();
+
+        if !self.open_days.contains(&local_start.weekday()) {
+            return false;
+        }
+
+        let midnight = local_start.date().and_time(NaiveTime::MIN);
+        let opens_at = midnight + Duration::hours(self.open_hour as i64);
+        let closes_at = midnight + Duration::hours(self.close_hour as i64);
+
+        local_start >= opens_at && local_end <= closes_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    /// Berlin's clocks go forward from 02:00 to 03:00 local time at 01:00 UTC on this Sunday
+    fn spring_forward(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2030, 3, 31, hour, minute, 0).unwrap()
+    }
+
+    fn hours(open_hour: u32, close_hour: u32) -> BusinessHours {
+        BusinessHours {
+            open_hour,
+            close_hour,
+            open_days: vec![Weekday::Sun],
+        }
+    }
+
+    #[test]
+    fn slots_crossing_a_spring_forward_gap_are_judged_by_their_local_ends() {
+        // 01:30 to 03:30 local, only an hour long but both ends are within hours
+        let start = spring_forward(0, 30);
+        assert!(hours(1, 4).contains(start, start + Duration::hours(1), &Berlin));
+
+        // 01:30 to 03:30 local ends after a 03:00 close
+        assert!(!hours(1, 3).contains(start, start + Duration::hours(1), &Berlin));
+    }
+
+    #[test]
+    fn hours_after_a_spring_forward_follow_the_new_local_clock() {
+        // 16:00 to 17:00 UTC is 18:00 to 19:00 local once the clocks have gone forward
+        let start = spring_forward(16, 0);
+        assert!(!hours(9, 18).contains(start, start + Duration::hours(1), &Berlin));
+        assert!(hours(9, 19).contains(start, start + Duration::hours(1), &Berlin));
+        assert!(hours(9, 18).contains(start - Duration::hours(1), start, &Berlin));
+    }
+}
@@ -1,3 +1,6 @@
+pub mod config;
 pub mod reservations;
+pub mod validation;
 
-pub use reservations::ReservationServiceImpl;
+pub use config::{BusinessHours, ServiceConfig};
+pub use reservations::{Clock, ReservationServiceImpl};
@@ -1,23 +1,155 @@
-use chrono::{DateTime, Utc};
+// Handlers and their helpers return `tonic::Status` as is so errors propagate with `?` into
+// the responses, boxing it would only add an allocation to every failed request
+#![allow(clippy::result_large_err)]
+
+use chrono::{
+    DateTime, Duration, LocalResult, NaiveDateTime, NaiveTime, Offset, SecondsFormat, TimeZone,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+use prost::Message;
 use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tonic::codegen::Bytes;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 
-use crate::db::{Client as DbClient, RepositoryError, ReservationRepository};
+use crate::db::{
+    AvailabilityRule as DbAvailabilityRule, BlackoutPeriod as DbBlackoutPeriod, Client as DbClient,
+    ClientCursor, ClientDeletionMode, ClientSort, ClientSortField, ClientStats as DbClientStats,
+    CreatedSeries, NewReservation, Page, PageCursor, RepositoryError,
+    ReservationChange as DbReservationChange, ReservationEvent as DbReservationEvent,
+    ReservationFilter, ReservationRepository, ReservationStatus, Resource as DbResource, SlotGrid,
+    TimeSlot as DbTimeSlot, WaitlistEntry as DbWaitlistEntry, DEFAULT_RESOURCE_ID,
+};
+use crate::middleware::constant_time_eq;
 use crate::proto::{
-    reservation_service_server::ReservationService, Client as ProtoClient, ClientId, ClientList,
-    ClientRequest, Reservation as ProtoReservation, ReservationId, ReservationList,
-    ReservationRequest, SlotList, TimeRange, TimeSlot as ProtoTimeSlot,
+    reservation_service_server::ReservationService, AddBlackoutRequest, Availability,
+    AvailabilityRule as ProtoAvailabilityRule, AvailabilityRuleList, Blackout as ProtoBlackout,
+    BlackoutId, BlackoutList, CancelReservationRequest, CheckAvailabilityBatchRequest,
+    CheckAvailabilityBatchResponse, CheckAvailabilityRequest, Client as ProtoClient, ClientId,
+    ClientList, ClientRequest, ClientStats as ProtoClientStats, Count, CountReservationsRequest,
+    CreateRecurringReservationRequest, CreateRecurringReservationResponse, CreateReservationResult,
+    CreateReservationsRequest, CreateReservationsResponse, DeleteClientRequest,
+    ExtendReservationRequest, FindClientByEmailRequest, FindFreeIntervalsRequest,
+    JoinWaitlistRequest, ListBlackoutsRequest, ListClientReservationsRequest, ListClientsRequest,
+    ListReservationsRequest, Recurrence, Reservation as ProtoReservation,
+    ReservationChange as ProtoReservationChange, ReservationEvent as ProtoReservationEvent,
+    ReservationEventList, ReservationId, ReservationList, ReservationReference, ReservationRequest,
+    Resource as ProtoResource, ResourceId, ResourceList, ResourceRequest, SearchClientsRequest,
+    SlotList, StreamReservationsRequest, TimeRange, TimeSlot as ProtoTimeSlot, UpdateClientRequest,
+    UpdateReservationNotesRequest, UpdateReservationRequest, WaitlistEntry as ProtoWaitlistEntry,
+    WaitlistEntryId, WaitlistEntryList, WatchReservationsRequest,
 };
 use prost_types::Timestamp;
 
+use super::{validation, ServiceConfig};
+
+/// Bounds of a time range filter, `None` leaves that side open
+type RangeBounds = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Slot length in minutes used when a `TimeRange` doesn't specify one
+const DEFAULT_SLOT_DURATION_MINUTES: i64 = 60;
+
+/// Upper bound on the number of candidate slot starts a single slot listing may lay out
+const MAX_SLOT_GRID_POINTS: i64 = 100_000;
+
+/// Page size used by paginated listings when the request doesn't specify one
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Upper bound on the page size a caller may request
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Start of the page tokens of client listings sorted by name
+const NAME_PAGE_TOKEN_PREFIX: &str = "name:";
+
+/// Upper bound on the number of occurrences in a recurring reservation
+const MAX_RECURRENCE_COUNT: u32 = 100;
+
+/// Upper bound on the days between occurrences of a recurring reservation
+const MAX_RECURRENCE_INTERVAL_DAYS: u32 = 366;
+
+/// How long a hold lasts when the request doesn't specify a TTL
+const DEFAULT_HOLD_TTL_SECONDS: u32 = 15 * 60;
+
+/// Upper bound on how long a hold may last
+const MAX_HOLD_TTL_SECONDS: u32 = 24 * 60 * 60;
+
+/// Upper bound on the number of reservations in a single batch create
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// Upper bound on the number of slots in a single batch availability check
+const MAX_AVAILABILITY_BATCH_SIZE: usize = 100;
+
+/// Fewest and most digits a phone number may have after the `+`, the latter set by E.164
+const MIN_PHONE_DIGITS: usize = 7;
+const MAX_PHONE_DIGITS: usize = 15;
+
+/// Shortest client search query accepted, shorter ones match too many clients to be useful
+const MIN_SEARCH_QUERY_LENGTH: usize = 2;
+
+/// Metadata key carrying the token for admin-only RPCs
+const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Metadata key naming who is making a change, recorded in the reservation audit trail
+const ACTOR_HEADER: &str = "x-actor";
+
+/// Number of messages buffered per streaming response before backpressure applies
+const STREAM_BUFFER_SIZE: usize = 32;
+
+/// Number of changes a WatchReservations subscriber may fall behind by before it skips ahead
+const CHANGE_FEED_CAPACITY: usize = 1024;
+
+/// Format of the opening and closing times in availability rules
+const AVAILABILITY_TIME_FORMAT: &str = "%H:%M";
+
+/// Most free slots suggested when a reservation's slot is already booked
+const MAX_SUGGESTIONS: usize = 3;
+
+/// How far before and after a booked slot alternatives are looked for
+const SUGGESTION_WINDOW_HOURS: i64 = 24;
+
+/// Shortest reservation that alternatives are suggested for
+const MIN_SUGGESTION_DURATION_MINUTES: i64 = 1;
+
+/// Source of the current time, replaceable so time-based rules can be exercised directly
+pub type Clock = Arc<dyn Fn() -> DateTime<Utc> + Send + Sync>;
+
 pub struct ReservationServiceImpl {
     repository: Arc<ReservationRepository>,
+    config: ServiceConfig,
+    clock: Clock,
+    changes: broadcast::Sender<DbReservationChange>,
 }
 
 impl ReservationServiceImpl {
-    pub fn new(repository: Arc<ReservationRepository>) -> Self {
-        Self { repository }
+    pub fn new(repository: Arc<ReservationRepository>, config: ServiceConfig) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_FEED_CAPACITY);
+
+        Self {
+            repository,
+            config,
+            clock: Arc::new(Utc::now),
+            changes,
+        }
+    }
+
+    /// Sender that reservation changes must be published on to reach WatchReservations
+    /// subscribers
+    pub fn change_feed(&self) -> broadcast::Sender<DbReservationChange> {
+        self.changes.clone()
+    }
+
+    /// Use `clock` instead of the system clock for the service's time-based rules
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        (self.clock)()
     }
 
     fn timestamp_to_datetime(ts: &Timestamp) -> DateTime<Utc> {
@@ -37,20 +169,163 @@ impl ReservationServiceImpl {
         ProtoTimeSlot {
             start_time: Some(Self::datetime_to_timestamp(&slot.start_time)),
             end_time: Some(Self::datetime_to_timestamp(&slot.end_time)),
+            remaining_capacity: 0,
+        }
+    }
+
+    fn db_conflicts_to_availability(conflicts: &crate::db::SlotConflicts) -> Availability {
+        Availability {
+            available: conflicts.is_empty(),
+            conflicting_reservation_ids: conflicts
+                .reservation_ids
+                .iter()
+                .map(Uuid::to_string)
+                .collect(),
+            unavailable_reason: conflicts.unavailable_reason.clone().unwrap_or_default(),
+        }
+    }
+
+    fn db_available_slot_to_proto(available: &crate::db::AvailableSlot) -> ProtoTimeSlot {
+        ProtoTimeSlot {
+            remaining_capacity: available.remaining_capacity as u32,
+            ..Self::db_timeslot_to_proto(&available.slot)
         }
     }
 
     fn db_reservation_to_proto(res: &crate::db::Reservation) -> ProtoReservation {
         ProtoReservation {
             id: res.id.to_string(),
+            reference: res.reference.clone(),
             client_id: res.client_id.to_string(),
+            resource_id: res.resource_id.to_string(),
             slot: Some(ProtoTimeSlot {
                 start_time: Some(Self::datetime_to_timestamp(&res.start_time)),
                 end_time: Some(Self::datetime_to_timestamp(&res.end_time)),
+                remaining_capacity: 0,
             }),
             created_at: Some(Self::datetime_to_timestamp(&res.created_at)),
-            status: String::from(res.status.clone()),
+            status: String::from(res.status),
             notes: res.notes.clone().unwrap_or_default(),
+            series_id: res.series_id.map(|id| id.to_string()).unwrap_or_default(),
+            expires_at: res.expires_at.as_ref().map(Self::datetime_to_timestamp),
+            cancellation_reason: res.cancellation_reason.clone().unwrap_or_default(),
+            checked_in_at: res.checked_in_at.as_ref().map(Self::datetime_to_timestamp),
+            updated_at: Some(Self::datetime_to_timestamp(&res.updated_at)),
+            version: res.version,
+            attendee_client_ids: res
+                .attendee_client_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect(),
+            is_attendee: false,
+        }
+    }
+
+    /// Convert a reservation listed for `client_id`, flagging it when they attend rather than own it
+    fn db_client_reservation_to_proto(
+        res: &crate::db::Reservation,
+        client_id: Uuid,
+    ) -> ProtoReservation {
+        ProtoReservation {
+            is_attendee: res.client_id != client_id,
+            ..Self::db_reservation_to_proto(res)
+        }
+    }
+
+    fn page_to_reservation_list(page: &Page<crate::db::Reservation>) -> ReservationList {
+        ReservationList {
+            reservations: page
+                .items
+                .iter()
+                .map(Self::db_reservation_to_proto)
+                .collect(),
+            next_page_token: page
+                .next_cursor
+                .as_ref()
+                .map(Self::encode_page_token)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn db_availability_rule_to_proto(rule: &DbAvailabilityRule) -> ProtoAvailabilityRule {
+        ProtoAvailabilityRule {
+            day_of_week: rule.day_of_week.number_from_monday(),
+            open_time: rule.open_time.format(AVAILABILITY_TIME_FORMAT).to_string(),
+            close_time: rule.close_time.format(AVAILABILITY_TIME_FORMAT).to_string(),
+        }
+    }
+
+    fn proto_availability_rule_to_db(
+        rule: &ProtoAvailabilityRule,
+    ) -> Result<DbAvailabilityRule, Status> {
+        let day_of_week = rule
+            .day_of_week
+            .checked_sub(1)
+            .and_then(|day| u8::try_from(day).ok())
+            .and_then(|day| Weekday::try_from(day).ok())
+            .ok_or_else(|| {
+                Status::invalid_argument("Day of week must be from 1 (Monday) to 7 (Sunday)")
+            })?;
+
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time, AVAILABILITY_TIME_FORMAT).map_err(|_| {
+                Status::invalid_argument(format!("Invalid time, expected HH:MM: {}", time))
+            })
+        };
+
+        Ok(DbAvailabilityRule {
+            day_of_week,
+            open_time: parse_time(&rule.open_time)?,
+            close_time: parse_time(&rule.close_time)?,
+        })
+    }
+
+    fn db_change_to_proto(change: &DbReservationChange) -> ProtoReservationChange {
+        ProtoReservationChange {
+            kind: change.kind.as_str().to_string(),
+            reservation: Some(Self::db_reservation_to_proto(&change.reservation)),
+        }
+    }
+
+    fn db_event_to_proto(event: &DbReservationEvent) -> ProtoReservationEvent {
+        ProtoReservationEvent {
+            id: event.id.to_string(),
+            reservation_id: event.reservation_id.to_string(),
+            old_status: event.old_status.map(String::from).unwrap_or_default(),
+            new_status: String::from(event.new_status),
+            actor: event.actor.clone().unwrap_or_default(),
+            created_at: Some(Self::datetime_to_timestamp(&event.created_at)),
+        }
+    }
+
+    fn db_waitlist_entry_to_proto(entry: &DbWaitlistEntry) -> ProtoWaitlistEntry {
+        ProtoWaitlistEntry {
+            id: entry.id.to_string(),
+            client_id: entry.client_id.to_string(),
+            resource_id: entry.resource_id.to_string(),
+            slot: Some(ProtoTimeSlot {
+                start_time: Some(Self::datetime_to_timestamp(&entry.start_time)),
+                end_time: Some(Self::datetime_to_timestamp(&entry.end_time)),
+                remaining_capacity: 0,
+            }),
+            created_at: Some(Self::datetime_to_timestamp(&entry.created_at)),
+        }
+    }
+
+    fn db_blackout_to_proto(blackout: &DbBlackoutPeriod) -> ProtoBlackout {
+        ProtoBlackout {
+            id: blackout.id.to_string(),
+            resource_id: blackout
+                .resource_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            slot: Some(ProtoTimeSlot {
+                start_time: Some(Self::datetime_to_timestamp(&blackout.start_time)),
+                end_time: Some(Self::datetime_to_timestamp(&blackout.end_time)),
+                remaining_capacity: 0,
+            }),
+            reason: blackout.reason.clone(),
+            created_at: Some(Self::datetime_to_timestamp(&blackout.created_at)),
         }
     }
 
@@ -59,12 +334,45 @@ impl ReservationServiceImpl {
             id: client.id.to_string(),
             name: client.name.clone(),
             email: client.email.clone(),
+            phone: client.phone.clone().unwrap_or_default(),
+            archived_at: client.archived_at.as_ref().map(Self::datetime_to_timestamp),
             created_at: Some(Self::datetime_to_timestamp(&client.created_at)),
         }
     }
 
+    fn db_client_stats_to_proto(client_id: Uuid, stats: &DbClientStats) -> ProtoClientStats {
+        ProtoClientStats {
+            client_id: client_id.to_string(),
+            total: stats.total as u64,
+            upcoming: stats.upcoming as u64,
+            cancelled: stats.cancelled as u64,
+            completed: stats.completed as u64,
+            no_shows: stats.no_shows as u64,
+        }
+    }
+
+    fn db_resource_to_proto(resource: &DbResource) -> ProtoResource {
+        ProtoResource {
+            id: resource.id.to_string(),
+            name: resource.name.clone(),
+            capacity: resource.capacity as u32,
+            active: resource.active,
+            created_at: Some(Self::datetime_to_timestamp(&resource.created_at)),
+        }
+    }
+
     fn map_error(err: RepositoryError) -> Status {
         match err {
+            // Losing the connection is transient, so let clients back off and retry
+            RepositoryError::DatabaseError(
+                e @ (sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed
+                | sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)),
+            ) => {
+                tracing::warn!("Database unavailable: {:?}", e);
+                Status::unavailable(format!("Database unavailable: {}", e))
+            }
             RepositoryError::DatabaseError(e) => {
                 tracing::error!("Database error: {:?}", e);
                 Status::internal(format!("Internal error: {}", e))
@@ -78,195 +386,3190 @@ impl ReservationServiceImpl {
             RepositoryError::ClientNotFound(id) => {
                 Status::not_found(format!("Client not found with ID: {}", id))
             }
+            RepositoryError::ClientArchived(id) => {
+                Status::failed_precondition(format!("Client with ID {} is archived", id))
+            }
+            RepositoryError::ClientEmailNotFound(email) => {
+                Status::not_found(format!("Client not found with email: {}", email))
+            }
+            RepositoryError::ClientHasUpcomingReservations { id, count } => {
+                Status::failed_precondition(format!(
+                    "Client with ID {} has {} upcoming reservations",
+                    id, count
+                ))
+            }
+            RepositoryError::ResourceNotFound(id) => {
+                Status::not_found(format!("Resource not found with ID: {}", id))
+            }
+            RepositoryError::ReservationReferenceNotFound(reference) => {
+                Status::not_found(format!("Reservation not found with reference: {}", reference))
+            }
+            RepositoryError::ResourceInactive(id) => {
+                Status::failed_precondition(format!("Resource with ID {} is deactivated", id))
+            }
+            RepositoryError::BlackedOut { reason, .. } => {
+                Status::failed_precondition(format!("Slot falls within a blackout: {}", reason))
+            }
+            RepositoryError::BlackoutNotFound(id) => {
+                Status::not_found(format!("Blackout not found with ID: {}", id))
+            }
+            RepositoryError::WaitlistEntryNotFound(id) => {
+                Status::not_found(format!("Waitlist entry not found with ID: {}", id))
+            }
+            RepositoryError::ReservationCancelled(id) => {
+                Status::failed_precondition(format!("Reservation with ID {} is cancelled", id))
+            }
+            RepositoryError::HoldExpired(id) => Status::failed_precondition(format!(
+                "Hold on reservation with ID {} has expired",
+                id
+            )),
+            RepositoryError::InvalidStatus { id, status } => {
+                Status::failed_precondition(format!("Reservation with ID {} is {}", id, status))
+            }
+            RepositoryError::ReservationNotOwned { id, client_id } => {
+                Status::permission_denied(format!(
+                    "Reservation with ID {} does not belong to client {}",
+                    id, client_id
+                ))
+            }
+            RepositoryError::VersionConflict(id) => Status::aborted(format!(
+                "Reservation with ID {} was modified since it was read, fetch it and retry",
+                id
+            )),
+            RepositoryError::ReservationNotEnded(id) => {
+                Status::failed_precondition(format!("Reservation with ID {} has not ended yet", id))
+            }
+            RepositoryError::OutsideCheckInWindow(id) => Status::failed_precondition(format!(
+                "Reservation with ID {} cannot be checked in at this time",
+                id
+            )),
+            RepositoryError::QuotaExceeded {
+                client_id,
+                count,
+                quota,
+            } => Status::resource_exhausted(format!(
+                "Client {} already has {} active reservations, the limit is {}",
+                client_id, count, quota
+            )),
+            RepositoryError::EndNotExtended { id, end_time } => {
+                Status::failed_precondition(format!(
+                    "Reservation with ID {} already ends at {}, use UpdateReservation to shorten it",
+                    id,
+                    end_time.to_rfc3339()
+                ))
+            }
+            RepositoryError::DuplicateEmail(email) => {
+                Status::already_exists(format!("A client with email {} already exists", email))
+            }
+            RepositoryError::BatchItemFailed { index, source } => {
+                let status = Self::map_error(*source);
+                Status::new(
+                    status.code(),
+                    format!("Reservation at index {}: {}", index, status.message()),
+                )
+            }
+            RepositoryError::SeriesConflict {
+                occurrence,
+                start_time,
+            } => Status::already_exists(format!(
+                "Occurrence {} starting at {} is already booked or blacked out",
+                occurrence,
+                start_time.to_rfc3339()
+            )),
         }
     }
-}
 
-#[tonic::async_trait]
-impl ReservationService for ReservationServiceImpl {
-    async fn list_available_slots(
-        &self,
-        request: Request<TimeRange>,
-    ) -> Result<Response<SlotList>, Status> {
-        let time_range = request.into_inner();
+    /// Parse an optional status filter, an empty string means no filter
+    fn parse_status_filter(status: &str) -> Result<Option<ReservationStatus>, Status> {
+        if status.is_empty() {
+            return Ok(None);
+        }
 
-        let start_time = match time_range.start_time {
-            Some(ts) => Self::timestamp_to_datetime(&ts),
-            None => return Err(Status::invalid_argument("Start time is required")),
-        };
+        status
+            .parse::<ReservationStatus>()
+            .map(Some)
+            .map_err(|err| Status::invalid_argument(err.to_string()))
+    }
 
-        let end_time = match time_range.end_time {
-            Some(ts) => Self::timestamp_to_datetime(&ts),
-            None => return Err(Status::invalid_argument("End time is required")),
+    /// Parse an optional time range filter where either bound may be left open
+    fn parse_range_filter(range: Option<TimeRange>) -> Result<RangeBounds, Status> {
+        let Some(range) = range else {
+            return Ok((None, None));
         };
 
-        if start_time >= end_time {
-            return Err(Status::invalid_argument(
-                "Start time must be before end time",
-            ));
-        }
-
-        let available_slots = self
-            .repository
-            .find_available_slots(start_time, end_time)
-            .await
-            .map_err(Self::map_error)?;
+        let start_time = range.start_time.as_ref().map(Self::timestamp_to_datetime);
+        let end_time = range.end_time.as_ref().map(Self::timestamp_to_datetime);
 
-        let proto_slots = available_slots
-            .iter()
-            .map(|slot| Self::db_timeslot_to_proto(slot))
-            .collect();
+        if let (Some(start), Some(end)) = (start_time, end_time) {
+            if start >= end {
+                return Err(Status::invalid_argument(
+                    "Start time must be before end time",
+                ));
+            }
+        }
 
-        Ok(Response::new(SlotList { slots: proto_slots }))
+        Ok((start_time, end_time))
     }
 
-    async fn create_reservation(
-        &self,
-        request: Request<ReservationRequest>,
-    ) -> Result<Response<ProtoReservation>, Status> {
-        let req = request.into_inner();
-
-        // Parse client ID
+    /// Parse the client and filters of a per-client reservation listing
+    fn parse_client_reservations_request(
+        req: &ListClientReservationsRequest,
+    ) -> Result<(Uuid, ReservationFilter), Status> {
         let client_id = req
             .client_id
             .parse::<Uuid>()
             .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
 
-        // Parse time slot
-        let slot = req
-            .slot
-            .ok_or(Status::invalid_argument("Time slot is required"))?;
-
-        let start_time = match slot.start_time {
-            Some(ts) => Self::timestamp_to_datetime(&ts),
-            None => return Err(Status::invalid_argument("Start time is required")),
+        let (start_time, end_time) = Self::parse_range_filter(req.range.clone())?;
+        let filter = ReservationFilter {
+            include_attended: true,
+            status: Self::parse_status_filter(&req.status)?,
+            start_time,
+            end_time,
+            ..Default::default()
         };
 
-        let end_time = match slot.end_time {
-            Some(ts) => Self::timestamp_to_datetime(&ts),
-            None => return Err(Status::invalid_argument("End time is required")),
-        };
+        Ok((client_id, filter))
+    }
 
-        if start_time >= end_time {
-            return Err(Status::invalid_argument(
-                "Start time must be before end time",
-            ));
-        }
+    /// Build a filter for listings across all clients, empty strings mean no filter
+    fn parse_reservation_filter(
+        client_id: &str,
+        resource_id: &str,
+        status: &str,
+        range: Option<TimeRange>,
+    ) -> Result<ReservationFilter, Status> {
+        let client_id = if client_id.is_empty() {
+            None
+        } else {
+            Some(
+                client_id
+                    .parse::<Uuid>()
+                    .map_err(|_| Status::invalid_argument("Invalid client ID format"))?,
+            )
+        };
 
-        let notes = if req.notes.is_empty() {
+        let resource_id = if resource_id.is_empty() {
             None
         } else {
-            Some(req.notes.as_str())
+            Some(Self::parse_resource_id(resource_id)?)
         };
-        let reservation = self
-            .repository
-            .create_reservation(client_id, start_time, end_time, notes)
-            .await
-            .map_err(Self::map_error)?;
 
-        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+        let (start_time, end_time) = Self::parse_range_filter(range)?;
+
+        Ok(ReservationFilter {
+            client_id,
+            resource_id,
+            status: Self::parse_status_filter(status)?,
+            start_time,
+            end_time,
+            ..Default::default()
+        })
     }
 
-    async fn get_reservation(
-        &self,
-        request: Request<ReservationId>,
-    ) -> Result<Response<ProtoReservation>, Status> {
-        let id = request
-            .into_inner()
-            .id
+    /// Parse the resource a request refers to, an empty ID means the default resource
+    fn parse_resource_id(resource_id: &str) -> Result<Uuid, Status> {
+        if resource_id.is_empty() {
+            return Ok(DEFAULT_RESOURCE_ID);
+        }
+
+        resource_id
             .parse::<Uuid>()
-            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+            .map_err(|_| Status::invalid_argument("Invalid resource ID format"))
+    }
 
-        let reservation = self
-            .repository
-            .get_reservation(id)
-            .await
-            .map_err(Self::map_error)?;
+    /// Check the version a caller expects a reservation to be at, versions start at 1 so
+    /// an unset one means the caller didn't read the reservation first
+    fn parse_expected_version(expected_version: i32) -> Result<i32, Status> {
+        if expected_version < 1 {
+            return Err(Status::invalid_argument(
+                "Expected reservation version is required",
+            ));
+        }
 
-        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+        Ok(expected_version)
     }
 
-    async fn cancel_reservation(
-        &self,
-        request: Request<ReservationId>,
-    ) -> Result<Response<()>, Status> {
-        let id = request
-            .into_inner()
-            .id
+    /// Parse the resource a blackout applies to, an empty ID means every resource
+    fn parse_blackout_resource_id(resource_id: &str) -> Result<Option<Uuid>, Status> {
+        if resource_id.is_empty() {
+            return Ok(None);
+        }
+
+        resource_id
             .parse::<Uuid>()
-            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+            .map(Some)
+            .map_err(|_| Status::invalid_argument("Invalid resource ID format"))
+    }
 
-        self.repository
-            .cancel_reservation(id)
-            .await
-            .map_err(Self::map_error)?;
+    /// Parse an IANA time zone name such as "Europe/Berlin", empty means UTC
+    fn parse_time_zone(time_zone: &str) -> Result<Tz, Status> {
+        if time_zone.is_empty() {
+            return Ok(Tz::UTC);
+        }
 
-        Ok(Response::new(()))
+        time_zone
+            .parse::<Tz>()
+            .map_err(|_| Status::invalid_argument(format!("Unknown time zone: {}", time_zone)))
     }
 
-    async fn list_client_reservations(
-        &self,
-        request: Request<ClientId>,
-    ) -> Result<Response<ReservationList>, Status> {
-        let client_id = request
-            .into_inner()
-            .id
-            .parse::<Uuid>()
-            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+    /// Resolve the requested page size, applying the default and the upper bound
+    fn page_size(requested: u32) -> i64 {
+        match requested {
+            0 => DEFAULT_PAGE_SIZE as i64,
+            size => size.min(MAX_PAGE_SIZE) as i64,
+        }
+    }
 
-        let reservations = self
-            .repository
-            .get_client_reservations(client_id)
-            .await
-            .map_err(Self::map_error)?;
+    fn encode_page_token(cursor: &PageCursor) -> String {
+        format!(
+            "{}_{}",
+            cursor
+                .timestamp
+                .to_rfc3339_opts(SecondsFormat::Micros, true),
+            cursor.id
+        )
+    }
 
-        let proto_reservations = reservations
-            .iter()
-            .map(|res| Self::db_reservation_to_proto(res))
-            .collect();
+    /// Decode a page token, an empty token means the first page
+    fn decode_page_token(token: &str) -> Result<Option<PageCursor>, Status> {
+        if token.is_empty() {
+            return Ok(None);
+        }
 
-        Ok(Response::new(ReservationList {
-            reservations: proto_reservations,
-        }))
-    }
+        let invalid = || Status::invalid_argument("Invalid page token");
+        let (timestamp, id) = token.split_once('_').ok_or_else(invalid)?;
 
-    async fn create_client(
-        &self,
-        request: Request<ClientRequest>,
-    ) -> Result<Response<ProtoClient>, Status> {
-        let req = request.into_inner();
+        let timestamp = DateTime::parse_from_rfc3339(timestamp)
+            .map_err(|_| invalid())?
+            .with_timezone(&Utc);
+        let id = id.parse::<Uuid>().map_err(|_| invalid())?;
 
-        if req.name.is_empty() {
-            return Err(Status::invalid_argument("Client name is required"));
+        Ok(Some(PageCursor { timestamp, id }))
+    }
+
+    /// Encode a client listing cursor, name cursors are told apart by their prefix as
+    /// timestamps never start with it
+    fn encode_client_page_token(cursor: &ClientCursor) -> String {
+        match cursor {
+            ClientCursor::CreatedAt { created_at, id } => Self::encode_page_token(&PageCursor {
+                timestamp: *created_at,
+                id: *id,
+            }),
+            ClientCursor::Name { name, id } => format!("{}{}_{}", NAME_PAGE_TOKEN_PREFIX, name, id),
         }
+    }
 
-        if req.email.is_empty() {
-            return Err(Status::invalid_argument("Client email is required"));
+    /// Decode a client listing page token, which must have been made for the same sort
+    fn decode_client_page_token(
+        token: &str,
+        field: ClientSortField,
+    ) -> Result<Option<ClientCursor>, Status> {
+        if token.is_empty() {
+            return Ok(None);
         }
 
-        let client = self
-            .repository
-            .create_client(&req.name, &req.email)
-            .await
-            .map_err(Self::map_error)?;
+        let cursor = match token.strip_prefix(NAME_PAGE_TOKEN_PREFIX) {
+            Some(rest) => {
+                // Names may contain underscores, IDs don't
+                let (name, id) = rest
+                    .rsplit_once('_')
+                    .ok_or_else(|| Status::invalid_argument("Invalid page token"))?;
+                let id = id
+                    .parse::<Uuid>()
+                    .map_err(|_| Status::invalid_argument("Invalid page token"))?;
+                ClientCursor::Name {
+                    name: name.to_string(),
+                    id,
+                }
+            }
+            None => {
+                let cursor = Self::decode_page_token(token)?
+                    .ok_or_else(|| Status::invalid_argument("Invalid page token"))?;
+                ClientCursor::CreatedAt {
+                    created_at: cursor.timestamp,
+                    id: cursor.id,
+                }
+            }
+        };
 
-        Ok(Response::new(Self::db_client_to_proto(&client)))
+        let matches = matches!(
+            (&cursor, field),
+            (ClientCursor::CreatedAt { .. }, ClientSortField::CreatedAt)
+                | (ClientCursor::Name { .. }, ClientSortField::Name)
+        );
+        if !matches {
+            return Err(Status::invalid_argument(
+                "Page token was made for a different sort",
+            ));
+        }
+
+        Ok(Some(cursor))
     }
 
-    async fn list_clients(&self, _: Request<()>) -> Result<Response<ClientList>, Status> {
-        let clients = self
-            .repository
-            .list_clients()
-            .await
-            .map_err(Self::map_error)?;
+    /// Validate a reservation request and convert it into the fields to insert
+    fn parse_reservation_request(
+        &self,
+        req: &ReservationRequest,
+    ) -> Result<NewReservation, Status> {
+        // Parse client ID
+        let client_id = req
+            .client_id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+        let resource_id = Self::parse_resource_id(&req.resource_id)?;
+
+        // Parse time slot
+        let (start_time, end_time) = Self::parse_time_slot(req.slot.clone())?;
+        self.validate_slot(start_time, end_time)?;
+        self.check_lead_time(start_time)?;
+        self.check_booking_horizon(start_time, end_time)?;
 
-        let proto_clients = clients
+        let mut attendee_client_ids = req
+            .attendee_client_ids
             .iter()
-            .map(|client| Self::db_client_to_proto(client))
-            .collect();
+            .map(|id| id.parse::<Uuid>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Status::invalid_argument("Invalid attendee client ID format"))?;
+        attendee_client_ids.sort();
+        attendee_client_ids.dedup();
+        attendee_client_ids.retain(|id| *id != client_id);
+
+        let notes = self.parse_notes(&req.notes)?;
+        let idempotency_key = if req.idempotency_key.is_empty() {
+            None
+        } else {
+            Some(req.idempotency_key.clone())
+        };
+
+        let hold_expires_at = if req.hold {
+            let ttl_seconds = match req.hold_ttl_seconds {
+                0 => DEFAULT_HOLD_TTL_SECONDS,
+                ttl => ttl.min(MAX_HOLD_TTL_SECONDS),
+            };
+            Some(self.now() + Duration::seconds(ttl_seconds as i64))
+        } else {
+            None
+        };
+
+        Ok(NewReservation {
+            client_id,
+            resource_id,
+            start_time,
+            end_time,
+            notes,
+            idempotency_key,
+            hold_expires_at,
+            attendee_client_ids,
+        })
+    }
+
+    /// Work out the slot of every occurrence of a recurrence, `None` when the reservation
+    /// doesn't repeat
+    ///
+    /// Occurrences are `interval_days` calendar days apart in `time_zone`, so a weekly 18:00
+    /// booking stays at 18:00 local time across a daylight saving change. The series stops
+    /// after `count` occurrences or at the last one starting by `until`, whichever comes first.
+    fn parse_recurrence(
+        recurrence: Option<&Recurrence>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        time_zone: &Tz,
+    ) -> Result<Option<Vec<DbTimeSlot>>, Status> {
+        let Some(recurrence) = recurrence else {
+            return Ok(None);
+        };
+
+        if recurrence.count <= 1 && recurrence.until.is_none() {
+            return Ok(None);
+        }
+
+        if recurrence.interval_days == 0 {
+            return Err(Status::invalid_argument(
+                "Recurrence interval is required when repeating",
+            ));
+        }
+        if recurrence.interval_days > MAX_RECURRENCE_INTERVAL_DAYS {
+            return Err(Status::invalid_argument(format!(
+                "Recurrence interval cannot exceed {} days",
+                MAX_RECURRENCE_INTERVAL_DAYS
+            )));
+        }
+        if recurrence.count > MAX_RECURRENCE_COUNT {
+            return Err(Status::invalid_argument(format!(
+                "Recurrence count cannot exceed {}",
+                MAX_RECURRENCE_COUNT
+            )));
+        }
+
+        let until = recurrence.until.as_ref().map(Self::timestamp_to_datetime);
+        if until.is_some_and(|until| until < start_time) {
+            return Err(Status::invalid_argument(
+                "Recurrence cannot end before the first occurrence",
+            ));
+        }
+
+        let past_latest_time =
+            || Status::invalid_argument("Recurrence reaches past the latest supported time");
+        let duration = end_time - start_time;
+        let local_start = start_time.with_timezone(time_zone).naive_local();
+
+        let mut occurrences = Vec::new();
+        for occurrence in 0u32.. {
+            if recurrence.count > 1 && occurrence >= recurrence.count {
+                break;
+            }
+
+            let start_time = if occurrence == 0 {
+                start_time
+            } else {
+                let days = Duration::days(recurrence.interval_days as i64 * occurrence as i64);
+                local_start
+                    .checked_add_signed(days)
+                    .and_then(|local| Self::local_to_utc(time_zone, local))
+                    .ok_or_else(past_latest_time)?
+            };
+            if until.is_some_and(|until| start_time > until) {
+                break;
+            }
+
+            if occurrence >= MAX_RECURRENCE_COUNT {
+                return Err(Status::invalid_argument(format!(
+                    "Recurrence count cannot exceed {}",
+                    MAX_RECURRENCE_COUNT
+                )));
+            }
+
+            // Every occurrence must be a representable time
+            let end_time = start_time
+                .checked_add_signed(duration)
+                .ok_or_else(past_latest_time)?;
+            occurrences.push(DbTimeSlot {
+                start_time,
+                end_time,
+            });
+        }
+
+        if occurrences.len() <= 1 {
+            return Ok(None);
+        }
+
+        Ok(Some(occurrences))
+    }
+
+    /// Instant a local time in `time_zone` refers to, the earlier one when the clocks fall
+    /// back and the time just after the gap when they spring forward past it
+    fn local_to_utc(time_zone: &Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match time_zone.from_local_datetime(&local) {
+            LocalResult::Single(instant) | LocalResult::Ambiguous(instant, _) => {
+                Some(instant.with_timezone(&Utc))
+            }
+            LocalResult::None => {
+                // Read the skipped time with the offset in force before the gap
+                let before = local.checked_sub_signed(Duration::days(1))?;
+                let offset = time_zone.offset_from_utc_datetime(&before).fix();
+                let utc =
+                    local.checked_sub_signed(Duration::seconds(offset.local_minus_utc() as i64))?;
+                Some(Utc.from_utc_datetime(&utc))
+            }
+        }
+    }
+
+    /// Check the occurrences of a recurring reservation against the availability rules and
+    /// create them
+    async fn create_series(
+        &self,
+        req: &ReservationRequest,
+        new_reservation: &NewReservation,
+        occurrences: &[DbTimeSlot],
+        skip_conflicts: bool,
+        actor: Option<String>,
+    ) -> Result<CreatedSeries, Status> {
+        // The first occurrence was checked along with the request, the last one is furthest out
+        if let Some(last) = occurrences.last() {
+            self.check_booking_horizon(last.start_time, last.end_time)?;
+        }
+
+        if !req.admin_override {
+            let slots: Vec<_> = occurrences
+                .iter()
+                .map(|occurrence| (occurrence.start_time, occurrence.end_time))
+                .collect();
+            self.check_availability_rules(&slots).await?;
+        }
+
+        self.repository
+            .create_recurring_reservation(
+                new_reservation,
+                occurrences,
+                skip_conflicts,
+                actor.as_deref(),
+            )
+            .await
+            .map_err(Self::map_error)
+    }
+
+    /// Trim reservation notes and check them against the length limit, blank notes mean none
+    fn parse_notes(&self, notes: &str) -> Result<Option<String>, Status> {
+        let notes = notes.trim();
+        if notes.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(max) = self.config.max_notes_length {
+            if notes.chars().count() > max {
+                return Err(Status::invalid_argument(format!(
+                    "Notes cannot be longer than {} characters",
+                    max
+                )));
+            }
+        }
+
+        Ok(Some(notes.to_string()))
+    }
+
+    /// Check a requested slot against the configured duration and alignment rules
+    fn validate_slot(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<(), Status> {
+        let duration = end_time - start_time;
+
+        if let Some(max) = self.config.max_reservation_duration {
+            if duration > max {
+                return Err(Status::invalid_argument(format!(
+                    "Reservation cannot be longer than {} minutes",
+                    max.num_minutes()
+                )));
+            }
+        }
+
+        if let Some(min) = self.config.min_reservation_duration {
+            if duration < min {
+                return Err(Status::invalid_argument(format!(
+                    "Reservation must be at least {} minutes long",
+                    min.num_minutes()
+                )));
+            }
+        }
+
+        if let Some(alignment) = self.config.start_alignment {
+            // The Unix epoch falls on midnight UTC, so this is alignment to the UTC clock
+            let is_aligned = start_time.timestamp_subsec_nanos() == 0
+                && start_time.timestamp() % alignment.num_seconds().max(1) == 0;
+
+            if !is_aligned {
+                return Err(Status::invalid_argument(format!(
+                    "Reservation must start on a multiple of {} minutes",
+                    alignment.num_minutes()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reject a request that doesn't carry the configured admin token
+    fn require_admin<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let Some(expected) = self.config.admin_token.as_deref() else {
+            return Err(Status::permission_denied("Admin operations are disabled"));
+        };
+
+        let valid = request
+            .metadata()
+            .get(ADMIN_TOKEN_HEADER)
+            .is_some_and(|value| constant_time_eq(value.as_bytes(), expected.as_bytes()));
+
+        if !valid {
+            return Err(Status::permission_denied(
+                "Admin token is missing or invalid",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Who the caller says is making the change, `None` when the metadata is missing or blank
+    fn actor<T>(request: &Request<T>) -> Option<String> {
+        request
+            .metadata()
+            .get(ACTOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|actor| !actor.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Returns true if `[start, end)` is within opening hours, which is always the case when
+    /// no availability rules are set
+    fn within_availability_rules(
+        &self,
+        rules: &[DbAvailabilityRule],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> bool {
+        let time_zone = self.config.availability_time_zone.unwrap_or(Tz::UTC);
+        rules.is_empty()
+            || rules
+                .iter()
+                .any(|rule| rule.contains(start, end, &time_zone))
+    }
+
+    /// Returns true if a slot is within business hours in `time_zone` and the availability
+    /// rules, and so may be offered to callers
+    fn is_offered(&self, rules: &[DbAvailabilityRule], slot: &DbTimeSlot, time_zone: &Tz) -> bool {
+        let within_business_hours = match &self.config.business_hours {
+            Some(hours) => hours.contains(slot.start_time, slot.end_time, time_zone),
+            None => true,
+        };
+
+        within_business_hours
+            && self.within_availability_rules(rules, slot.start_time, slot.end_time)
+    }
+
+    /// Find up to `MAX_SUGGESTIONS` free slots as long as a conflicting reservation, starting
+    /// closest to it within `SUGGESTION_WINDOW_HOURS` either side, in chronological order
+    async fn suggest_alternatives(&self, new: &NewReservation) -> Result<Vec<DbTimeSlot>, Status> {
+        let duration = new.end_time - new.start_time;
+        let window = Duration::hours(SUGGESTION_WINDOW_HOURS);
+
+        // Laying out slots shorter than this across the window would take forever
+        if duration < Duration::minutes(MIN_SUGGESTION_DURATION_MINUTES) {
+            return Ok(Vec::new());
+        }
+
+        // Slots are laid out a whole number of durations from the requested start so they
+        // stay aligned with it, and can't start before the lead time allows
+        let earliest = match self.config.min_lead_time {
+            Some(lead_time) => self.now() + lead_time,
+            None => self.now(),
+        };
+        let mut start = new.start_time - window;
+        if start < earliest {
+            let behind = (earliest - start).num_milliseconds();
+            let step = duration.num_milliseconds();
+            let steps = (behind + step - 1) / step;
+            start += duration * steps as i32;
+        }
+
+        let end = new.end_time + window;
+        let end = match self.latest_bookable_end(duration) {
+            Some(latest) => end.min(latest),
+            None => end,
+        };
+
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let rules = self
+            .repository
+            .list_availability_rules()
+            .await
+            .map_err(Self::map_error)?;
+
+        let time_zone = self.config.availability_time_zone.unwrap_or(Tz::UTC);
+        let grid = SlotGrid {
+            duration,
+            step: duration,
+            alignment_offset: None,
+            time_zone,
+        };
+
+        let mut slots: Vec<DbTimeSlot> = self
+            .repository
+            .find_available_slots(new.resource_id, start, end, &grid, None, |slot| {
+                self.is_offered(&rules, slot, &time_zone)
+            })
+            .await
+            .map_err(Self::map_error)?
+            .into_iter()
+            .map(|available| available.slot)
+            .collect();
+
+        slots.sort_by_key(|slot| (slot.start_time - new.start_time).abs());
+        slots.truncate(MAX_SUGGESTIONS);
+        slots.sort_by_key(|slot| slot.start_time);
+
+        Ok(slots)
+    }
+
+    /// Attach free alternatives to the status of a create that hit a booked slot, as an
+    /// encoded `SlotList` in the status details
+    async fn conflict_with_suggestions(&self, new: &NewReservation) -> Status {
+        let status = Self::map_error(RepositoryError::ReservationConflict);
+
+        match self.suggest_alternatives(new).await {
+            Ok(slots) => {
+                let suggestions = SlotList {
+                    slots: slots.iter().map(Self::db_timeslot_to_proto).collect(),
+                };
+                Status::with_details(
+                    status.code(),
+                    status.message(),
+                    Bytes::from(suggestions.encode_to_vec()),
+                )
+            }
+            Err(e) => {
+                tracing::warn!("Failed to suggest alternative slots: {}", e.message());
+                status
+            }
+        }
+    }
+
+    /// Reject slots outside the availability rules when they are enforced for reservations
+    async fn check_availability_rules(
+        &self,
+        slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    ) -> Result<(), Status> {
+        if !self.config.enforce_availability_rules {
+            return Ok(());
+        }
+
+        let rules = self
+            .repository
+            .list_availability_rules()
+            .await
+            .map_err(Self::map_error)?;
+
+        for &(start, end) in slots {
+            self.check_slot_within_rules(&rules, start, end)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reject a slot outside the already loaded availability `rules`
+    fn check_slot_within_rules(
+        &self,
+        rules: &[DbAvailabilityRule],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(), Status> {
+        if !self.within_availability_rules(rules, start, end) {
+            return Err(Status::failed_precondition(format!(
+                "Reservation from {} to {} is outside opening hours",
+                start.to_rfc3339_opts(SecondsFormat::Secs, true),
+                end.to_rfc3339_opts(SecondsFormat::Secs, true)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Reject a slot starting sooner than the configured lead time from now
+    ///
+    /// A lead time of zero checks nothing, the same as none at all.
+    fn check_lead_time(&self, start_time: DateTime<Utc>) -> Result<(), Status> {
+        let lead_time = self
+            .config
+            .min_lead_time
+            .filter(|lead_time| *lead_time > Duration::zero());
+        if let Some(lead_time) = lead_time {
+            if start_time - self.now() < lead_time {
+                return Err(Status::failed_precondition(format!(
+                    "Reservations must be made at least {} minutes before they start",
+                    lead_time.num_minutes()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reject a slot further ahead than the configured limits, which are the booking horizon
+    /// for its end and the advance booking limit for its start
+    fn check_booking_horizon(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<(), Status> {
+        if let Some(horizon) = self.config.booking_horizon {
+            if end_time > self.now() + horizon {
+                return Err(Status::failed_precondition(format!(
+                    "Reservations cannot be made more than {} days ahead",
+                    horizon.num_days()
+                )));
+            }
+        }
+
+        if let Some(max_advance) = self.config.max_advance {
+            if start_time > self.now() + max_advance {
+                return Err(Status::invalid_argument(format!(
+                    "Reservations cannot start more than {} days ahead",
+                    max_advance.num_days()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Latest time a slot lasting `duration` may end by to pass `check_booking_horizon`,
+    /// `None` when neither limit is configured
+    fn latest_bookable_end(&self, duration: Duration) -> Option<DateTime<Utc>> {
+        let by_end = self
+            .config
+            .booking_horizon
+            .map(|horizon| self.now() + horizon);
+        let by_start = self
+            .config
+            .max_advance
+            .map(|max_advance| self.now() + max_advance + duration);
+
+        by_end.into_iter().chain(by_start).min()
+    }
+
+    /// Reject cancelling at `now` when the reservation starts less than `cutoff` later
+    ///
+    /// Cancelling exactly `cutoff` before the start is still allowed.
+    fn check_cancellation_cutoff(
+        start_time: DateTime<Utc>,
+        now: DateTime<Utc>,
+        cutoff: Duration,
+    ) -> Result<(), Status> {
+        if now > start_time - cutoff {
+            return Err(Status::failed_precondition(format!(
+                "Reservations cannot be cancelled less than {} minutes before they start",
+                cutoff.num_minutes()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Validate a new client's details, returning the normalized email and phone
+    fn parse_client_request(req: &ClientRequest) -> Result<(String, Option<String>), Status> {
+        if req.name.is_empty() {
+            return Err(Status::invalid_argument("Client name is required"));
+        }
+
+        if req.email.is_empty() {
+            return Err(Status::invalid_argument("Client email is required"));
+        }
+
+        // Normalize so the same address is always stored the same way
+        let email = validation::normalize_email(&req.email);
+        if !validation::is_valid_email(&email) {
+            return Err(Status::invalid_argument(format!(
+                "Invalid email address: {}",
+                req.email
+            )));
+        }
+
+        let phone = Self::parse_phone(&req.phone)?;
+
+        Ok((email, phone))
+    }
+
+    /// Normalize a phone number by dropping spaces, dashes, dots and parentheses, a blank
+    /// number means none
+    ///
+    /// Numbers must be in international format, a `+` followed by up to 15 digits.
+    fn parse_phone(phone: &str) -> Result<Option<String>, Status> {
+        let normalized: String = phone
+            .chars()
+            .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '.' | '(' | ')'))
+            .collect();
+        if normalized.is_empty() {
+            return Ok(None);
+        }
+
+        let valid = normalized.strip_prefix('+').is_some_and(|digits| {
+            (MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&digits.len())
+                && digits.chars().all(|c| c.is_ascii_digit())
+        });
+        if !valid {
+            return Err(Status::invalid_argument(format!(
+                "Invalid phone number: {}, expected international format such as +15551234567",
+                phone
+            )));
+        }
+
+        Ok(Some(normalized))
+    }
+
+    /// Extract and validate the start and end times of a requested slot
+    fn parse_time_slot(
+        slot: Option<ProtoTimeSlot>,
+    ) -> Result<(DateTime<Utc>, DateTime<Utc>), Status> {
+        let slot = slot.ok_or(Status::invalid_argument("Time slot is required"))?;
+
+        let start_time = match slot.start_time {
+            Some(ts) => Self::timestamp_to_datetime(&ts),
+            None => return Err(Status::invalid_argument("Start time is required")),
+        };
+
+        let end_time = match slot.end_time {
+            Some(ts) => Self::timestamp_to_datetime(&ts),
+            None => return Err(Status::invalid_argument("End time is required")),
+        };
+
+        if start_time >= end_time {
+            return Err(Status::invalid_argument(
+                "Start time must be before end time",
+            ));
+        }
+
+        Ok((start_time, end_time))
+    }
+}
+
+#[tonic::async_trait]
+impl ReservationService for ReservationServiceImpl {
+    async fn list_available_slots(
+        &self,
+        request: Request<TimeRange>,
+    ) -> Result<Response<SlotList>, Status> {
+        let time_range = request.into_inner();
+
+        let start_time = match time_range.start_time {
+            Some(ts) => Self::timestamp_to_datetime(&ts),
+            None => return Err(Status::invalid_argument("Start time is required")),
+        };
+
+        let end_time = match time_range.end_time {
+            Some(ts) => Self::timestamp_to_datetime(&ts),
+            None => return Err(Status::invalid_argument("End time is required")),
+        };
+
+        if start_time >= end_time {
+            return Err(Status::invalid_argument(
+                "Start time must be before end time",
+            ));
+        }
+
+        let slot_duration = if time_range.slot_duration_minutes == 0 {
+            Duration::minutes(DEFAULT_SLOT_DURATION_MINUTES)
+        } else {
+            Duration::minutes(time_range.slot_duration_minutes as i64)
+        };
+
+        let step = match time_range.step_minutes {
+            0 => slot_duration,
+            minutes => Duration::minutes(minutes as i64),
+        };
+
+        // Without either option slots keep starting right at the start of the range
+        let alignment_offset =
+            if time_range.step_minutes == 0 && time_range.alignment_offset_minutes == 0 {
+                None
+            } else {
+                let offset = Duration::minutes(time_range.alignment_offset_minutes as i64);
+                if offset >= step {
+                    return Err(Status::invalid_argument(
+                        "Alignment offset must be less than the step",
+                    ));
+                }
+                Some(offset)
+            };
+
+        let resource_id = Self::parse_resource_id(&time_range.resource_id)?;
+        let time_zone = Self::parse_time_zone(&time_range.time_zone)?;
+
+        let grid = SlotGrid {
+            duration: slot_duration,
+            step,
+            alignment_offset,
+            time_zone,
+        };
+
+        // Don't offer slots that creating a reservation would refuse
+        let end_time = match self.latest_bookable_end(slot_duration) {
+            Some(latest) => end_time.min(latest),
+            None => end_time,
+        };
+
+        // Every grid point is visited even when few slots turn out to be free
+        if (end_time - start_time).num_seconds() / step.num_seconds() > MAX_SLOT_GRID_POINTS {
+            return Err(Status::invalid_argument(format!(
+                "Time range spans more than {} slot starts, narrow it or use a longer step",
+                MAX_SLOT_GRID_POINTS
+            )));
+        }
+
+        let max_results = match time_range.max_results {
+            0 => None,
+            max => Some(max as usize),
+        };
+
+        let rules = self
+            .repository
+            .list_availability_rules()
+            .await
+            .map_err(Self::map_error)?;
+
+        let available_slots = self
+            .repository
+            .find_available_slots(
+                resource_id,
+                start_time,
+                end_time,
+                &grid,
+                max_results,
+                |slot| self.is_offered(&rules, slot, &time_zone),
+            )
+            .await
+            .map_err(Self::map_error)?;
+
+        let proto_slots = available_slots
+            .iter()
+            .map(Self::db_available_slot_to_proto)
+            .collect();
+
+        Ok(Response::new(SlotList { slots: proto_slots }))
+    }
+
+    async fn find_free_intervals(
+        &self,
+        request: Request<FindFreeIntervalsRequest>,
+    ) -> Result<Response<SlotList>, Status> {
+        let req = request.into_inner();
+
+        let start_time = match req.start_time {
+            Some(ts) => Self::timestamp_to_datetime(&ts),
+            None => return Err(Status::invalid_argument("Start time is required")),
+        };
+
+        let end_time = match req.end_time {
+            Some(ts) => Self::timestamp_to_datetime(&ts),
+            None => return Err(Status::invalid_argument("End time is required")),
+        };
+
+        if start_time >= end_time {
+            return Err(Status::invalid_argument(
+                "Start time must be before end time",
+            ));
+        }
+
+        let resource_id = Self::parse_resource_id(&req.resource_id)?;
+
+        let intervals = self
+            .repository
+            .find_free_intervals(resource_id, start_time, end_time)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(SlotList {
+            slots: intervals.iter().map(Self::db_timeslot_to_proto).collect(),
+        }))
+    }
+
+    async fn check_availability(
+        &self,
+        request: Request<CheckAvailabilityRequest>,
+    ) -> Result<Response<Availability>, Status> {
+        let req = request.into_inner();
+
+        let (start_time, end_time) = Self::parse_time_slot(req.slot)?;
+        let resource_id = Self::parse_resource_id(&req.resource_id)?;
+
+        let conflicts = self
+            .repository
+            .find_slot_conflicts(resource_id, start_time, end_time)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_conflicts_to_availability(
+            &conflicts,
+        )))
+    }
+
+    async fn check_availability_batch(
+        &self,
+        request: Request<CheckAvailabilityBatchRequest>,
+    ) -> Result<Response<CheckAvailabilityBatchResponse>, Status> {
+        let req = request.into_inner();
+
+        if req.slots.len() > MAX_AVAILABILITY_BATCH_SIZE {
+            return Err(Status::invalid_argument(format!(
+                "Cannot check more than {} slots at once",
+                MAX_AVAILABILITY_BATCH_SIZE
+            )));
+        }
+
+        let resource_id = Self::parse_resource_id(&req.resource_id)?;
+
+        let slots = req
+            .slots
+            .into_iter()
+            .enumerate()
+            .map(|(index, slot)| {
+                let (start_time, end_time) =
+                    Self::parse_time_slot(Some(slot)).map_err(|status| {
+                        Status::invalid_argument(format!(
+                            "Slot at index {}: {}",
+                            index,
+                            status.message()
+                        ))
+                    })?;
+                Ok(crate::db::TimeSlot {
+                    start_time,
+                    end_time,
+                })
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        let conflicts = self
+            .repository
+            .find_slot_conflicts_batch(resource_id, &slots)
+            .await
+            .map_err(Self::map_error)?;
+
+        let results = conflicts
+            .iter()
+            .map(Self::db_conflicts_to_availability)
+            .collect();
+
+        Ok(Response::new(CheckAvailabilityBatchResponse { results }))
+    }
+
+    async fn create_reservation(
+        &self,
+        request: Request<ReservationRequest>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        if request.get_ref().admin_override {
+            self.require_admin(&request)?;
+        }
+
+        let actor = Self::actor(&request);
+        let req = request.into_inner();
+
+        let new_reservation = self.parse_reservation_request(&req)?;
+
+        if let Some(occurrences) = Self::parse_recurrence(
+            req.recurrence.as_ref(),
+            new_reservation.start_time,
+            new_reservation.end_time,
+            &self.config.availability_time_zone.unwrap_or(Tz::UTC),
+        )? {
+            let series = self
+                .create_series(&req, &new_reservation, &occurrences, false, actor)
+                .await?;
+
+            let first = series
+                .reservations
+                .first()
+                .ok_or_else(|| Status::internal("Recurring reservation created no occurrences"))?;
+
+            return Ok(Response::new(Self::db_reservation_to_proto(first)));
+        }
+
+        if !req.admin_override {
+            self.check_availability_rules(&[(
+                new_reservation.start_time,
+                new_reservation.end_time,
+            )])
+            .await?;
+        }
+
+        let reservation = match self
+            .repository
+            .create_reservation(&new_reservation, actor.as_deref())
+            .await
+        {
+            Ok(reservation) => reservation,
+            Err(RepositoryError::ReservationConflict) if !req.skip_suggestions => {
+                return Err(self.conflict_with_suggestions(&new_reservation).await);
+            }
+            Err(e) => return Err(Self::map_error(e)),
+        };
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn create_recurring_reservation(
+        &self,
+        request: Request<CreateRecurringReservationRequest>,
+    ) -> Result<Response<CreateRecurringReservationResponse>, Status> {
+        if request
+            .get_ref()
+            .reservation
+            .as_ref()
+            .is_some_and(|req| req.admin_override)
+        {
+            self.require_admin(&request)?;
+        }
+
+        let actor = Self::actor(&request);
+        let req = request.into_inner();
+        let reservation = req
+            .reservation
+            .ok_or_else(|| Status::invalid_argument("Reservation is required"))?;
+
+        let new_reservation = self.parse_reservation_request(&reservation)?;
+        let occurrences = Self::parse_recurrence(
+            reservation.recurrence.as_ref(),
+            new_reservation.start_time,
+            new_reservation.end_time,
+            &self.config.availability_time_zone.unwrap_or(Tz::UTC),
+        )?
+        .ok_or_else(|| {
+            Status::invalid_argument("Recurrence must produce more than one occurrence")
+        })?;
+
+        let series = self
+            .create_series(
+                &reservation,
+                &new_reservation,
+                &occurrences,
+                req.skip_conflicts,
+                actor,
+            )
+            .await?;
+
+        Ok(Response::new(CreateRecurringReservationResponse {
+            reservations: series
+                .reservations
+                .iter()
+                .map(Self::db_reservation_to_proto)
+                .collect(),
+            skipped: series
+                .skipped
+                .iter()
+                .map(Self::db_timeslot_to_proto)
+                .collect(),
+        }))
+    }
+
+    async fn create_reservations(
+        &self,
+        request: Request<CreateReservationsRequest>,
+    ) -> Result<Response<CreateReservationsResponse>, Status> {
+        if request
+            .get_ref()
+            .reservations
+            .iter()
+            .any(|item| item.admin_override)
+        {
+            self.require_admin(&request)?;
+        }
+
+        let actor = Self::actor(&request);
+        let req = request.into_inner();
+
+        if req.reservations.len() > MAX_BATCH_SIZE {
+            return Err(Status::invalid_argument(format!(
+                "A batch cannot contain more than {} reservations",
+                MAX_BATCH_SIZE
+            )));
+        }
+
+        let with_index = |index: usize, status: Status| {
+            Status::new(
+                status.code(),
+                format!("Reservation at index {}: {}", index, status.message()),
+            )
+        };
+
+        let new_reservations = req
+            .reservations
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                if item.recurrence.as_ref().is_some_and(|r| r.count > 1) {
+                    return Err(with_index(
+                        index,
+                        Status::invalid_argument("Recurrence is not supported in batches"),
+                    ));
+                }
+
+                self.parse_reservation_request(item)
+                    .map_err(|status| with_index(index, status))
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        // The rules are loaded once for the whole batch, only when some item has to follow them
+        let enforced = |item: &ReservationRequest| {
+            self.config.enforce_availability_rules && !item.admin_override
+        };
+        let rules = if req.reservations.iter().any(enforced) {
+            self.repository
+                .list_availability_rules()
+                .await
+                .map_err(Self::map_error)?
+        } else {
+            Vec::new()
+        };
+
+        // Items outside opening hours are reported in their own result when skipping failures
+        let mut rejected = Vec::with_capacity(new_reservations.len());
+        for (index, (item, new)) in req.reservations.iter().zip(&new_reservations).enumerate() {
+            let outcome = if enforced(item) {
+                self.check_slot_within_rules(&rules, new.start_time, new.end_time)
+            } else {
+                Ok(())
+            };
+
+            match outcome {
+                Ok(()) => rejected.push(None),
+                Err(status) if req.skip_failures => rejected.push(Some(status)),
+                Err(status) => return Err(with_index(index, status)),
+            }
+        }
+
+        let accepted: Vec<NewReservation> = new_reservations
+            .into_iter()
+            .zip(&rejected)
+            .filter(|(_, rejection)| rejection.is_none())
+            .map(|(new, _)| new)
+            .collect();
+
+        let mut created = self
+            .repository
+            .create_reservations(&accepted, req.skip_failures, actor.as_deref())
+            .await
+            .map_err(Self::map_error)?
+            .into_iter();
+
+        let results = rejected
+            .into_iter()
+            .enumerate()
+            .map(|(index, rejection)| {
+                let result = match rejection {
+                    Some(status) => Err(status),
+                    None => created
+                        .next()
+                        .ok_or_else(|| Status::internal("Batch returned too few results"))
+                        .and_then(|result| result.map_err(Self::map_error)),
+                };
+
+                match result {
+                    Ok(reservation) => CreateReservationResult {
+                        index: index as u32,
+                        reservation: Some(Self::db_reservation_to_proto(&reservation)),
+                        code: tonic::Code::Ok as i32,
+                        message: String::new(),
+                    },
+                    Err(status) => CreateReservationResult {
+                        index: index as u32,
+                        reservation: None,
+                        code: status.code() as i32,
+                        message: status.message().to_string(),
+                    },
+                }
+            })
+            .collect();
+
+        Ok(Response::new(CreateReservationsResponse { results }))
+    }
+
+    async fn get_reservation(
+        &self,
+        request: Request<ReservationId>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let reservation = self
+            .repository
+            .get_reservation(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn get_reservation_by_reference(
+        &self,
+        request: Request<ReservationReference>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        // Codes read out over the phone often come back in lower case or with stray spaces
+        let reference = request.into_inner().reference.trim().to_uppercase();
+        if reference.is_empty() {
+            return Err(Status::invalid_argument(
+                "Reservation reference is required",
+            ));
+        }
+
+        let reservation = self
+            .repository
+            .get_reservation_by_reference(&reference)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn list_reservation_events(
+        &self,
+        request: Request<ReservationId>,
+    ) -> Result<Response<ReservationEventList>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let events = self
+            .repository
+            .list_reservation_events(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(ReservationEventList {
+            events: events.iter().map(Self::db_event_to_proto).collect(),
+        }))
+    }
+
+    async fn confirm_reservation(
+        &self,
+        request: Request<ReservationId>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let actor = Self::actor(&request);
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let reservation = self
+            .repository
+            .confirm_reservation(id, actor.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn delete_reservation(
+        &self,
+        request: Request<ReservationId>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        self.require_admin(&request)?;
+
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let reservation = self
+            .repository
+            .delete_reservation(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn cancel_reservation(
+        &self,
+        request: Request<CancelReservationRequest>,
+    ) -> Result<Response<()>, Status> {
+        let actor = Self::actor(&request);
+        let req = request.into_inner();
+
+        let id = req
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+        let expected_version = Self::parse_expected_version(req.expected_version)?;
+        let client_id = if req.client_id.is_empty() {
+            None
+        } else {
+            Some(
+                req.client_id
+                    .parse::<Uuid>()
+                    .map_err(|_| Status::invalid_argument("Invalid client ID format"))?,
+            )
+        };
+
+        if !req.admin_override {
+            if let Some(cutoff) = self.config.cancellation_cutoff {
+                let reservation = self
+                    .repository
+                    .get_reservation(id)
+                    .await
+                    .map_err(Self::map_error)?;
+
+                Self::check_cancellation_cutoff(reservation.start_time, self.now(), cutoff)?;
+            }
+        }
+
+        let reason = if req.reason.is_empty() {
+            None
+        } else {
+            Some(req.reason.as_str())
+        };
+
+        self.repository
+            .cancel_reservation(
+                id,
+                client_id,
+                expected_version,
+                req.entire_series,
+                reason,
+                actor.as_deref(),
+            )
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn check_in(
+        &self,
+        request: Request<ReservationId>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let reservation = self
+            .repository
+            .check_in(id, self.now())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn mark_no_show(
+        &self,
+        request: Request<ReservationId>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let actor = Self::actor(&request);
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let reservation = self
+            .repository
+            .mark_no_show(id, actor.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn reinstate_reservation(
+        &self,
+        request: Request<ReservationId>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let actor = Self::actor(&request);
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let reservation = self
+            .repository
+            .reinstate_reservation(id, actor.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn update_reservation(
+        &self,
+        request: Request<UpdateReservationRequest>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let actor = Self::actor(&request);
+        let req = request.into_inner();
+
+        let id = req
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let expected_version = Self::parse_expected_version(req.expected_version)?;
+
+        let (start_time, end_time) = Self::parse_time_slot(req.slot)?;
+        self.validate_slot(start_time, end_time)?;
+        self.check_lead_time(start_time)?;
+        self.check_booking_horizon(start_time, end_time)?;
+
+        let reservation = self
+            .repository
+            .reschedule_reservation(id, expected_version, start_time, end_time, actor.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn extend_reservation(
+        &self,
+        request: Request<ExtendReservationRequest>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let actor = Self::actor(&request);
+        let req = request.into_inner();
+
+        let id = req
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let end_time = match req.end_time {
+            Some(ts) => Self::timestamp_to_datetime(&ts),
+            None => return Err(Status::invalid_argument("End time is required")),
+        };
+
+        // The extended slot has to satisfy the same rules as a newly booked one
+        let existing = self
+            .repository
+            .get_reservation(id)
+            .await
+            .map_err(Self::map_error)?;
+        if end_time > existing.end_time {
+            self.validate_slot(existing.start_time, end_time)?;
+            self.check_booking_horizon(existing.start_time, end_time)?;
+        }
+
+        let reservation = self
+            .repository
+            .extend_reservation(id, end_time, actor.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn update_reservation_notes(
+        &self,
+        request: Request<UpdateReservationNotesRequest>,
+    ) -> Result<Response<ProtoReservation>, Status> {
+        let req = request.into_inner();
+
+        let id = req
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid reservation ID format"))?;
+
+        let notes = self.parse_notes(&req.notes)?;
+
+        let reservation = self
+            .repository
+            .update_notes(id, notes.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_reservation_to_proto(&reservation)))
+    }
+
+    async fn join_waitlist(
+        &self,
+        request: Request<JoinWaitlistRequest>,
+    ) -> Result<Response<ProtoWaitlistEntry>, Status> {
+        let req = request.into_inner();
+
+        let client_id = req
+            .client_id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+        let resource_id = Self::parse_resource_id(&req.resource_id)?;
+
+        // The slot must be one that could be booked directly once it frees up
+        let (start_time, end_time) = Self::parse_time_slot(req.slot)?;
+        self.validate_slot(start_time, end_time)?;
+        self.check_lead_time(start_time)?;
+        self.check_booking_horizon(start_time, end_time)?;
+
+        let entry = self
+            .repository
+            .join_waitlist(client_id, resource_id, start_time, end_time)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_waitlist_entry_to_proto(&entry)))
+    }
+
+    async fn list_waitlist(
+        &self,
+        request: Request<ClientId>,
+    ) -> Result<Response<WaitlistEntryList>, Status> {
+        let client_id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+        let entries = self
+            .repository
+            .list_waitlist(client_id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(WaitlistEntryList {
+            entries: entries
+                .iter()
+                .map(Self::db_waitlist_entry_to_proto)
+                .collect(),
+        }))
+    }
+
+    async fn leave_waitlist(
+        &self,
+        request: Request<WaitlistEntryId>,
+    ) -> Result<Response<()>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid waitlist entry ID format"))?;
+
+        self.repository
+            .leave_waitlist(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn add_blackout(
+        &self,
+        request: Request<AddBlackoutRequest>,
+    ) -> Result<Response<ProtoBlackout>, Status> {
+        self.require_admin(&request)?;
+        let req = request.into_inner();
+
+        let resource_id = Self::parse_blackout_resource_id(&req.resource_id)?;
+        let (start_time, end_time) = Self::parse_time_slot(req.slot)?;
+        if start_time >= end_time {
+            return Err(Status::invalid_argument(
+                "Start time must be before end time",
+            ));
+        }
+
+        let reason = req.reason.trim();
+        if reason.is_empty() {
+            return Err(Status::invalid_argument("Blackout reason is required"));
+        }
+
+        let blackout = self
+            .repository
+            .add_blackout(resource_id, start_time, end_time, reason)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_blackout_to_proto(&blackout)))
+    }
+
+    async fn list_blackouts(
+        &self,
+        request: Request<ListBlackoutsRequest>,
+    ) -> Result<Response<BlackoutList>, Status> {
+        let resource_id = Self::parse_blackout_resource_id(&request.into_inner().resource_id)?;
+
+        let blackouts = self
+            .repository
+            .list_blackouts(resource_id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(BlackoutList {
+            blackouts: blackouts.iter().map(Self::db_blackout_to_proto).collect(),
+        }))
+    }
+
+    async fn remove_blackout(&self, request: Request<BlackoutId>) -> Result<Response<()>, Status> {
+        self.require_admin(&request)?;
+
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid blackout ID format"))?;
+
+        self.repository
+            .remove_blackout(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn list_conflicting_reservations(
+        &self,
+        request: Request<BlackoutId>,
+    ) -> Result<Response<ReservationList>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid blackout ID format"))?;
+
+        let reservations = self
+            .repository
+            .list_blackout_conflicts(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(ReservationList {
+            reservations: reservations
+                .iter()
+                .map(Self::db_reservation_to_proto)
+                .collect(),
+            next_page_token: String::new(),
+        }))
+    }
+
+    async fn list_client_reservations(
+        &self,
+        request: Request<ListClientReservationsRequest>,
+    ) -> Result<Response<ReservationList>, Status> {
+        let req = request.into_inner();
+
+        let (client_id, filter) = Self::parse_client_reservations_request(&req)?;
+        let after = Self::decode_page_token(&req.page_token)?;
+
+        let page = self
+            .repository
+            .get_client_reservations(client_id, &filter, Self::page_size(req.page_size), after)
+            .await
+            .map_err(Self::map_error)?;
+
+        let list = ReservationList {
+            reservations: page
+                .items
+                .iter()
+                .map(|res| Self::db_client_reservation_to_proto(res, client_id))
+                .collect(),
+            next_page_token: page
+                .next_cursor
+                .as_ref()
+                .map(Self::encode_page_token)
+                .unwrap_or_default(),
+        };
+
+        Ok(Response::new(list))
+    }
+
+    type StreamClientReservationsStream = ReceiverStream<Result<ProtoReservation, Status>>;
+
+    async fn stream_client_reservations(
+        &self,
+        request: Request<ListClientReservationsRequest>,
+    ) -> Result<Response<Self::StreamClientReservationsStream>, Status> {
+        let req = request.into_inner();
+
+        let (client_id, filter) = Self::parse_client_reservations_request(&req)?;
+        let after = Self::decode_page_token(&req.page_token)?;
+        let limit = Self::page_size(req.page_size);
+
+        // Fetch the first page up front so an unknown client fails the call itself
+        let mut page = self
+            .repository
+            .get_client_reservations(client_id, &filter, limit, after)
+            .await
+            .map_err(Self::map_error)?;
+
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let repository = self.repository.clone();
+        let filter = ReservationFilter {
+            client_id: Some(client_id),
+            ..filter
+        };
+
+        tokio::spawn(async move {
+            loop {
+                for res in &page.items {
+                    // Stop paging once the client disconnects
+                    if tx
+                        .send(Ok(Self::db_client_reservation_to_proto(res, client_id)))
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+
+                let Some(cursor) = page.next_cursor.take() else {
+                    break;
+                };
+
+                page = match repository
+                    .list_reservations(&filter, limit, Some(cursor))
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(err) => {
+                        let _ = tx.send(Err(Self::map_error(err))).await;
+                        return;
+                    }
+                };
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type WatchReservationsStream = ReceiverStream<Result<ProtoReservationChange, Status>>;
+
+    async fn watch_reservations(
+        &self,
+        request: Request<WatchReservationsRequest>,
+    ) -> Result<Response<Self::WatchReservationsStream>, Status> {
+        let req = request.into_inner();
+
+        let client_id = if req.client_id.is_empty() {
+            None
+        } else {
+            let client_id = req
+                .client_id
+                .parse::<Uuid>()
+                .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+            // Fail the call itself for an unknown client instead of streaming nothing
+            self.repository
+                .get_client(client_id)
+                .await
+                .map_err(Self::map_error)?;
+
+            Some(client_id)
+        };
+
+        let mut changes = self.changes.subscribe();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+
+        tokio::spawn(async move {
+            loop {
+                let change = tokio::select! {
+                    change = changes.recv() => change,
+                    // Stop once the client disconnects, even while no changes arrive
+                    _ = tx.closed() => return,
+                };
+
+                // A subscriber that can't keep up misses changes rather than holding up others
+                let change = match change {
+                    Ok(change) => change,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Watch subscriber fell behind, skipping changes");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+
+                if let Some(client_id) = client_id {
+                    let res = &change.reservation;
+                    if res.client_id != client_id && !res.attendee_client_ids.contains(&client_id) {
+                        continue;
+                    }
+                }
+
+                if tx
+                    .send(Ok(Self::db_change_to_proto(&change)))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn list_reservations(
+        &self,
+        request: Request<ListReservationsRequest>,
+    ) -> Result<Response<ReservationList>, Status> {
+        let req = request.into_inner();
+
+        let filter = Self::parse_reservation_filter(
+            &req.client_id,
+            &req.resource_id,
+            &req.status,
+            req.range,
+        )?;
+        let after = Self::decode_page_token(&req.page_token)?;
+
+        let page = self
+            .repository
+            .list_reservations(&filter, Self::page_size(req.page_size), after)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::page_to_reservation_list(&page)))
+    }
+
+    type StreamReservationsStream = ReceiverStream<Result<ProtoReservation, Status>>;
+
+    async fn stream_reservations(
+        &self,
+        request: Request<StreamReservationsRequest>,
+    ) -> Result<Response<Self::StreamReservationsStream>, Status> {
+        let req = request.into_inner();
+
+        let filter = Self::parse_reservation_filter(
+            &req.client_id,
+            &req.resource_id,
+            &req.status,
+            req.range,
+        )?;
+
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let repository = self.repository.clone();
+
+        tokio::spawn(async move {
+            let mut reservations = repository.stream_reservations(&filter);
+
+            while let Some(result) = reservations.next().await {
+                let item = result
+                    .map(|res| Self::db_reservation_to_proto(&res))
+                    .map_err(Self::map_error);
+
+                // The receiver is gone once the client disconnects, dropping the
+                // database stream here stops the underlying query
+                if tx.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn count_reservations(
+        &self,
+        request: Request<CountReservationsRequest>,
+    ) -> Result<Response<Count>, Status> {
+        let req = request.into_inner();
+
+        let filter = Self::parse_reservation_filter(
+            &req.client_id,
+            &req.resource_id,
+            &req.status,
+            req.range,
+        )?;
+
+        let count = self
+            .repository
+            .count_reservations(&filter)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Count {
+            count: count as u64,
+        }))
+    }
+
+    async fn create_client(
+        &self,
+        request: Request<ClientRequest>,
+    ) -> Result<Response<ProtoClient>, Status> {
+        let req = request.into_inner();
+        let (email, phone) = Self::parse_client_request(&req)?;
+
+        let client = self
+            .repository
+            .create_client(&req.name, &email, phone.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_to_proto(&client)))
+    }
+
+    async fn get_or_create_client(
+        &self,
+        request: Request<ClientRequest>,
+    ) -> Result<Response<ProtoClient>, Status> {
+        let req = request.into_inner();
+        let (email, phone) = Self::parse_client_request(&req)?;
+
+        let client = self
+            .repository
+            .get_or_create_client(&req.name, &email, phone.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_to_proto(&client)))
+    }
+
+    async fn update_client(
+        &self,
+        request: Request<UpdateClientRequest>,
+    ) -> Result<Response<ProtoClient>, Status> {
+        let req = request.into_inner();
+
+        let id = req
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+        if req.name.as_deref().is_some_and(str::is_empty) {
+            return Err(Status::invalid_argument("Client name cannot be empty"));
+        }
+
+        let email = match req.email.as_deref() {
+            Some(email) => {
+                let normalized = validation::normalize_email(email);
+                if normalized.is_empty() {
+                    return Err(Status::invalid_argument("Client email cannot be empty"));
+                }
+                if !validation::is_valid_email(&normalized) {
+                    return Err(Status::invalid_argument(format!(
+                        "Invalid email address: {}",
+                        email
+                    )));
+                }
+                Some(normalized)
+            }
+            None => None,
+        };
+
+        let phone = req.phone.as_deref().map(Self::parse_phone).transpose()?;
+
+        let client = self
+            .repository
+            .update_client(
+                id,
+                req.name.as_deref(),
+                email.as_deref(),
+                phone.as_ref().map(Option::as_deref),
+            )
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_to_proto(&client)))
+    }
+
+    async fn get_client(
+        &self,
+        request: Request<ClientId>,
+    ) -> Result<Response<ProtoClient>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+        let client = self
+            .repository
+            .get_client(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_to_proto(&client)))
+    }
+
+    async fn get_client_stats(
+        &self,
+        request: Request<ClientId>,
+    ) -> Result<Response<ProtoClientStats>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+        let stats = self
+            .repository
+            .get_client_stats(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_stats_to_proto(id, &stats)))
+    }
+
+    async fn archive_client(
+        &self,
+        request: Request<ClientId>,
+    ) -> Result<Response<ProtoClient>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+        let client = self
+            .repository
+            .archive_client(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_to_proto(&client)))
+    }
+
+    async fn unarchive_client(
+        &self,
+        request: Request<ClientId>,
+    ) -> Result<Response<ProtoClient>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+        let client = self
+            .repository
+            .unarchive_client(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_to_proto(&client)))
+    }
+
+    async fn find_client_by_email(
+        &self,
+        request: Request<FindClientByEmailRequest>,
+    ) -> Result<Response<ProtoClient>, Status> {
+        let email = request.into_inner().email;
+        if email.trim().is_empty() {
+            return Err(Status::invalid_argument("Email is required"));
+        }
+
+        let client = self
+            .repository
+            .find_client_by_email(&email)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_client_to_proto(&client)))
+    }
+
+    async fn delete_client(
+        &self,
+        request: Request<DeleteClientRequest>,
+    ) -> Result<Response<()>, Status> {
+        let actor = Self::actor(&request);
+        let req = request.into_inner();
+
+        let id = req
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid client ID format"))?;
+
+        let mode = match req.mode.as_str() {
+            "" | "refuse" => ClientDeletionMode::Refuse,
+            "cascade" => ClientDeletionMode::Cascade,
+            other => {
+                return Err(Status::invalid_argument(format!(
+                    "Invalid deletion mode: {}",
+                    other
+                )))
+            }
+        };
+
+        self.repository
+            .delete_client(id, mode, actor.as_deref())
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn list_clients(
+        &self,
+        request: Request<ListClientsRequest>,
+    ) -> Result<Response<ClientList>, Status> {
+        let req = request.into_inner();
+
+        let sort = ClientSort {
+            field: match req.sort_by.as_str() {
+                "" | "created_at" => ClientSortField::CreatedAt,
+                "name" => ClientSortField::Name,
+                other => {
+                    return Err(Status::invalid_argument(format!(
+                        "Invalid sort field: {}",
+                        other
+                    )))
+                }
+            },
+            descending: req.descending,
+        };
+        let after = Self::decode_client_page_token(&req.page_token, sort.field)?;
+
+        let page = self
+            .repository
+            .list_clients(
+                Self::page_size(req.page_size),
+                after,
+                sort,
+                req.include_archived,
+            )
+            .await
+            .map_err(Self::map_error)?;
+
+        let proto_clients = page.items.iter().map(Self::db_client_to_proto).collect();
 
         Ok(Response::new(ClientList {
             clients: proto_clients,
+            next_page_token: page
+                .next_cursor
+                .as_ref()
+                .map(Self::encode_client_page_token)
+                .unwrap_or_default(),
+        }))
+    }
+
+    async fn search_clients(
+        &self,
+        request: Request<SearchClientsRequest>,
+    ) -> Result<Response<ClientList>, Status> {
+        let req = request.into_inner();
+
+        let query = req.query.trim();
+        if query.chars().count() < MIN_SEARCH_QUERY_LENGTH {
+            return Err(Status::invalid_argument(format!(
+                "Search query must be at least {} characters",
+                MIN_SEARCH_QUERY_LENGTH
+            )));
+        }
+
+        let clients = self
+            .repository
+            .search_clients(query, Self::page_size(req.limit))
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(ClientList {
+            clients: clients.iter().map(Self::db_client_to_proto).collect(),
+            next_page_token: String::new(),
+        }))
+    }
+
+    async fn count_clients(&self, _request: Request<()>) -> Result<Response<Count>, Status> {
+        let count = self
+            .repository
+            .count_clients()
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Count {
+            count: count as u64,
+        }))
+    }
+
+    async fn create_resource(
+        &self,
+        request: Request<ResourceRequest>,
+    ) -> Result<Response<ProtoResource>, Status> {
+        let req = request.into_inner();
+
+        if req.name.is_empty() {
+            return Err(Status::invalid_argument("Resource name is required"));
+        }
+
+        let capacity = match req.capacity {
+            0 => 1,
+            capacity => i32::try_from(capacity)
+                .map_err(|_| Status::invalid_argument("Resource capacity is too large"))?,
+        };
+
+        let resource = self
+            .repository
+            .create_resource(&req.name, capacity)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_resource_to_proto(&resource)))
+    }
+
+    async fn get_resource(
+        &self,
+        request: Request<ResourceId>,
+    ) -> Result<Response<ProtoResource>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid resource ID format"))?;
+
+        let resource = self
+            .repository
+            .get_resource(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_resource_to_proto(&resource)))
+    }
+
+    async fn deactivate_resource(
+        &self,
+        request: Request<ResourceId>,
+    ) -> Result<Response<ProtoResource>, Status> {
+        let id = request
+            .into_inner()
+            .id
+            .parse::<Uuid>()
+            .map_err(|_| Status::invalid_argument("Invalid resource ID format"))?;
+
+        let resource = self
+            .repository
+            .deactivate_resource(id)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(Self::db_resource_to_proto(&resource)))
+    }
+
+    async fn set_availability_rules(
+        &self,
+        request: Request<AvailabilityRuleList>,
+    ) -> Result<Response<AvailabilityRuleList>, Status> {
+        self.require_admin(&request)?;
+
+        let mut rules = request
+            .into_inner()
+            .rules
+            .iter()
+            .map(Self::proto_availability_rule_to_db)
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        rules.sort_by_key(|rule| (rule.day_of_week.number_from_monday(), rule.open_time));
+        if rules.windows(2).any(|pair| {
+            pair[0].day_of_week == pair[1].day_of_week && pair[0].open_time == pair[1].open_time
+        }) {
+            return Err(Status::invalid_argument(
+                "Rules cannot open at the same time on the same day",
+            ));
+        }
+
+        let rules = self
+            .repository
+            .set_availability_rules(&rules)
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(AvailabilityRuleList {
+            rules: rules
+                .iter()
+                .map(Self::db_availability_rule_to_proto)
+                .collect(),
+        }))
+    }
+
+    async fn get_availability_rules(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<AvailabilityRuleList>, Status> {
+        let rules = self
+            .repository
+            .list_availability_rules()
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(AvailabilityRuleList {
+            rules: rules
+                .iter()
+                .map(Self::db_availability_rule_to_proto)
+                .collect(),
+        }))
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<ResourceList>, Status> {
+        let resources = self
+            .repository
+            .list_resources()
+            .await
+            .map_err(Self::map_error)?;
+
+        Ok(Response::new(ResourceList {
+            resources: resources.iter().map(Self::db_resource_to_proto).collect(),
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::TestDatabase;
+    use crate::db::Reservation as DbReservation;
+    use sqlx::postgres::PgPoolOptions;
+    use tonic::Code;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2030, 1, 7, hour, minute, 0).unwrap()
+    }
+
+    /// A service stopped at `now` whose database is never connected to, for the checks made
+    /// before reaching the repository
+    fn service(config: ServiceConfig, now: DateTime<Utc>) -> ReservationServiceImpl {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        ReservationServiceImpl::new(Arc::new(ReservationRepository::new(pool)), config)
+            .with_clock(Arc::new(move || now))
+    }
+
+    /// A service stopped at `now` on a throwaway database, along with its repository
+    async fn db_service(
+        config: ServiceConfig,
+        now: DateTime<Utc>,
+    ) -> (
+        TestDatabase,
+        Arc<ReservationRepository>,
+        ReservationServiceImpl,
+    ) {
+        let db = TestDatabase::new().await;
+        let repository = Arc::new(ReservationRepository::new(db.pool.clone()));
+        let service = ReservationServiceImpl::new(repository.clone(), config)
+            .with_clock(Arc::new(move || now));
+        (db, repository, service)
+    }
+
+    async fn create_reservation(
+        repository: &ReservationRepository,
+        client_id: Uuid,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> DbReservation {
+        let new = NewReservation {
+            client_id,
+            resource_id: DEFAULT_RESOURCE_ID,
+            start_time,
+            end_time,
+            notes: None,
+            idempotency_key: None,
+            hold_expires_at: None,
+            attendee_client_ids: Vec::new(),
+        };
+        repository.create_reservation(&new, None).await.unwrap()
+    }
+
+    fn slot(start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> ProtoTimeSlot {
+        ProtoTimeSlot {
+            start_time: Some(ReservationServiceImpl::datetime_to_timestamp(&start_time)),
+            end_time: Some(ReservationServiceImpl::datetime_to_timestamp(&end_time)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_slot_accepts_exactly_the_minimum_duration() {
+        let service = service(
+            ServiceConfig {
+                min_reservation_duration: Some(Duration::minutes(30)),
+                ..Default::default()
+            },
+            at(9, 0),
+        );
+
+        assert!(service.validate_slot(at(10, 0), at(10, 30)).is_ok());
+
+        let status = service.validate_slot(at(10, 0), at(10, 29)).unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(status.message().contains("at least 30 minutes"));
+
+        let status = service
+            .validate_slot(at(10, 0), at(10, 0) + Duration::seconds(1))
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn validate_slot_accepts_exactly_the_maximum_duration() {
+        let service = service(
+            ServiceConfig {
+                max_reservation_duration: Some(Duration::hours(2)),
+                ..Default::default()
+            },
+            at(9, 0),
+        );
+
+        assert!(service.validate_slot(at(10, 0), at(12, 0)).is_ok());
+
+        let status = service.validate_slot(at(10, 0), at(12, 1)).unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn validate_slot_requires_aligned_starts() {
+        let service = service(
+            ServiceConfig {
+                start_alignment: Some(Duration::minutes(15)),
+                ..Default::default()
+            },
+            at(9, 0),
+        );
+
+        assert!(service.validate_slot(at(10, 0), at(11, 0)).is_ok());
+        assert!(service.validate_slot(at(10, 45), at(11, 43)).is_ok());
+
+        for start in [at(10, 17), at(10, 0) + Duration::seconds(1)] {
+            let status = service
+                .validate_slot(start, start + Duration::hours(1))
+                .unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+            assert!(status.message().contains("multiple of 15 minutes"));
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_slot_without_rules_accepts_any_slot() {
+        let service = service(ServiceConfig::default(), at(9, 0));
+
+        assert!(service
+            .validate_slot(at(10, 17), at(10, 17) + Duration::seconds(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn cancellation_cutoff_allows_cancelling_exactly_at_the_cutoff() {
+        let cutoff = Duration::hours(2);
+        let start = at(12, 0);
+
+        assert!(ReservationServiceImpl::check_cancellation_cutoff(start, at(9, 0), cutoff).is_ok());
+        assert!(
+            ReservationServiceImpl::check_cancellation_cutoff(start, at(10, 0), cutoff).is_ok()
+        );
+
+        let status = ReservationServiceImpl::check_cancellation_cutoff(start, at(10, 1), cutoff)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert!(status.message().contains("120 minutes"));
+    }
+
+    #[tokio::test]
+    async fn cancel_reservation_applies_the_cutoff_unless_overridden() {
+        let config = ServiceConfig {
+            cancellation_cutoff: Some(Duration::hours(2)),
+            ..Default::default()
+        };
+        let (_db, repository, service) = db_service(config, at(10, 0)).await;
+
+        let client = repository
+            .create_client("Test Client", "alice@example.com", None)
+            .await
+            .unwrap();
+        let reservations = [
+            create_reservation(&repository, client.id, at(12, 0), at(13, 0)).await,
+            create_reservation(&repository, client.id, at(11, 59), at(12, 0)).await,
+        ];
+        let cancel = |id: Uuid, admin_override: bool| {
+            Request::new(CancelReservationRequest {
+                id: id.to_string(),
+                expected_version: 1,
+                admin_override,
+                ..Default::default()
+            })
+        };
+
+        // Exactly two hours ahead is still in time
+        service
+            .cancel_reservation(cancel(reservations[0].id, false))
+            .await
+            .unwrap();
+
+        let status = service
+            .cancel_reservation(cancel(reservations[1].id, false))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        service
+            .cancel_reservation(cancel(reservations[1].id, true))
+            .await
+            .unwrap();
+        for reservation in &reservations {
+            let cancelled = repository.get_reservation(reservation.id).await.unwrap();
+            assert_eq!(cancelled.status, ReservationStatus::Cancelled);
+        }
+    }
+
+    #[tokio::test]
+    async fn create_client_with_a_taken_email_already_exists() {
+        let (_db, _, service) = db_service(ServiceConfig::default(), at(9, 0)).await;
+        let request = |email: &str| {
+            Request::new(ClientRequest {
+                name: "Test Client".to_string(),
+                email: email.to_string(),
+                ..Default::default()
+            })
+        };
+
+        service
+            .create_client(request("alice@example.com"))
+            .await
+            .unwrap();
+
+        let status = service
+            .create_client(request(" alice@EXAMPLE.com"))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn batch_skipping_failures_reports_out_of_hours_items_individually() {
+        let config = ServiceConfig {
+            enforce_availability_rules: true,
+            ..Default::default()
+        };
+        let (_db, repository, service) = db_service(config, at(9, 0)).await;
+        let client = repository
+            .create_client("Test Client", "alice@example.com", None)
+            .await
+            .unwrap();
+        repository
+            .set_availability_rules(&[DbAvailabilityRule {
+                day_of_week: Weekday::Mon,
+                open_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                close_time: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            }])
+            .await
+            .unwrap();
+        let item = |start_hour, end_hour| ReservationRequest {
+            client_id: client.id.to_string(),
+            slot: Some(slot(at(start_hour, 0), at(end_hour, 0))),
+            ..Default::default()
+        };
+
+        let response = service
+            .create_reservations(Request::new(CreateReservationsRequest {
+                reservations: vec![item(13, 14), item(10, 11)],
+                skip_failures: true,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.results[0].code, Code::FailedPrecondition as i32);
+        assert!(response.results[0].reservation.is_none());
+        assert_eq!(response.results[1].code, Code::Ok as i32);
+        assert!(response.results[1].reservation.is_some());
+    }
+
+    #[tokio::test]
+    async fn sub_second_conflict_gets_no_suggestions() {
+        let (_db, repository, service) = db_service(ServiceConfig::default(), at(9, 0)).await;
+        let client = repository
+            .create_client("Test Client", "alice@example.com", None)
+            .await
+            .unwrap();
+        create_reservation(&repository, client.id, at(10, 0), at(11, 0)).await;
+
+        let request = ReservationRequest {
+            client_id: client.id.to_string(),
+            slot: Some(slot(at(10, 0), at(10, 0) + Duration::milliseconds(500))),
+            ..Default::default()
+        };
+        let status = service
+            .create_reservation(Request::new(request))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::AlreadyExists);
+        let suggestions = SlotList::decode(status.details()).unwrap();
+        assert!(suggestions.slots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn suggestions_follow_business_hours_in_the_availability_time_zone() {
+        let config = ServiceConfig {
+            business_hours: Some(crate::service::BusinessHours {
+                open_hour: 9,
+                close_hour: 17,
+                open_days: vec![Weekday::Mon],
+            }),
+            availability_time_zone: Some(chrono_tz::Europe::Berlin),
+            ..Default::default()
+        };
+        let (_db, repository, service) = db_service(config, at(7, 0)).await;
+        let alice = repository
+            .create_client("Alice", "alice@example.com", None)
+            .await
+            .unwrap();
+        let bob = repository
+            .create_client("Bob", "bob@example.com", None)
+            .await
+            .unwrap();
+        create_reservation(&repository, alice.id, at(15, 0), at(16, 0)).await;
+
+        let request = ReservationRequest {
+            client_id: bob.id.to_string(),
+            slot: Some(slot(at(15, 0), at(16, 0))),
+            ..Default::default()
+        };
+        let status = service
+            .create_reservation(Request::new(request))
+            .await
+            .unwrap_err();
+
+        // Berlin closes at 16:00 UTC in winter, so the hour after the conflict isn't offered
+        let suggestions = SlotList::decode(status.details()).unwrap();
+        let starts: Vec<_> = suggestions
+            .slots
+            .iter()
+            .map(|slot| {
+                ReservationServiceImpl::timestamp_to_datetime(slot.start_time.as_ref().unwrap())
+            })
+            .collect();
+        assert_eq!(starts, vec![at(12, 0), at(13, 0), at(14, 0)]);
+    }
+
+    #[tokio::test]
+    async fn list_available_slots_rejects_ranges_with_too_many_slot_starts() {
+        let service = service(ServiceConfig::default(), at(9, 0));
+        let request = |days| {
+            Request::new(TimeRange {
+                start_time: Some(ReservationServiceImpl::datetime_to_timestamp(&at(10, 0))),
+                end_time: Some(ReservationServiceImpl::datetime_to_timestamp(
+                    &(at(10, 0) + Duration::days(days)),
+                )),
+                slot_duration_minutes: 1,
+                step_minutes: 1,
+                max_results: 1,
+                ..Default::default()
+            })
+        };
+
+        // A year of one minute steps is refused however few results are asked for
+        let status = service
+            .list_available_slots(request(365))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn parse_recurrence_rejects_intervals_beyond_a_year() {
+        let recurrence = |interval_days| Recurrence {
+            count: 2,
+            interval_days,
+            ..Default::default()
+        };
+
+        let parsed = ReservationServiceImpl::parse_recurrence(
+            Some(&recurrence(366)),
+            at(10, 0),
+            at(11, 0),
+            &Tz::UTC,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].start_time, at(10, 0) + Duration::days(366));
+
+        for interval_days in [367, 1_000_000_000] {
+            let status = ReservationServiceImpl::parse_recurrence(
+                Some(&recurrence(interval_days)),
+                at(10, 0),
+                at(11, 0),
+                &Tz::UTC,
+            )
+            .unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn parse_recurrence_rejects_series_past_the_latest_time() {
+        let recurrence = Recurrence {
+            count: 2,
+            interval_days: 7,
+            ..Default::default()
+        };
+        let end_time = DateTime::<Utc>::MAX_UTC - Duration::days(1);
+
+        let status = ReservationServiceImpl::parse_recurrence(
+            Some(&recurrence),
+            end_time - Duration::hours(1),
+            end_time,
+            &Tz::UTC,
+        )
+        .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn parse_recurrence_keeps_the_local_time_across_daylight_saving_changes() {
+        let berlin = chrono_tz::Europe::Berlin;
+        let weekly = |count| Recurrence {
+            count,
+            interval_days: 7,
+            ..Default::default()
+        };
+        let local = |month, day, hour| {
+            berlin
+                .with_ymd_and_hms(2030, month, day, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        // 18:00 every Monday either side of the clocks going forward on 31 March
+        let start = local(3, 25, 18);
+        let occurrences = ReservationServiceImpl::parse_recurrence(
+            Some(&weekly(2)),
+            start,
+            start + Duration::hours(1),
+            &berlin,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(occurrences[1].start_time, local(4, 1, 18));
+        assert_eq!(occurrences[1].end_time, local(4, 1, 19));
+
+        // And either side of the clocks going back on 27 October
+        let start = local(10, 21, 18);
+        let occurrences = ReservationServiceImpl::parse_recurrence(
+            Some(&weekly(2)),
+            start,
+            start + Duration::hours(1),
+            &berlin,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(occurrences[1].start_time, local(10, 28, 18));
+
+        // A 02:30 start skipped by the clocks going forward moves past the gap
+        let start = local(3, 24, 2) + Duration::minutes(30);
+        let occurrences = ReservationServiceImpl::parse_recurrence(
+            Some(&weekly(2)),
+            start,
+            start + Duration::hours(1),
+            &berlin,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            occurrences[1].start_time,
+            local(3, 31, 3) + Duration::minutes(30)
+        );
+    }
+
+    #[tokio::test]
+    async fn recurring_reservation_must_end_within_the_booking_horizon() {
+        let now = at(9, 0);
+        let service = service(
+            ServiceConfig {
+                booking_horizon: Some(Duration::days(90)),
+                ..Default::default()
+            },
+            now,
+        );
+        let request = |count| {
+            Request::new(CreateRecurringReservationRequest {
+                reservation: Some(ReservationRequest {
+                    client_id: Uuid::new_v4().to_string(),
+                    slot: Some(slot(at(10, 0), at(11, 0))),
+                    recurrence: Some(Recurrence {
+                        count,
+                        interval_days: 7,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        };
+
+        // The first occurrence is well within the horizon, the 52nd is a year out
+        let status = service
+            .create_recurring_reservation(request(52))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert!(status.message().contains("90 days"));
+    }
+
+    fn lead_time_config(minutes: i64) -> ServiceConfig {
+        ServiceConfig {
+            min_lead_time: Some(Duration::minutes(minutes)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn lead_time_accepts_a_start_exactly_the_lead_time_away() {
+        let now = at(9, 0);
+        let service = service(lead_time_config(15), now);
+
+        assert!(service.check_lead_time(now + Duration::minutes(15)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn lead_time_rejects_a_start_one_minute_inside_it() {
+        let now = at(9, 0);
+        let service = service(lead_time_config(15), now);
+
+        let status = service
+            .check_lead_time(now + Duration::minutes(14))
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert!(status.message().contains("15 minutes"));
+
+        // The handler turns the request down before reaching the repository
+        let request = ReservationRequest {
+            client_id: Uuid::new_v4().to_string(),
+            slot: Some(slot(
+                now + Duration::minutes(14),
+                now + Duration::minutes(74),
+            )),
+            ..Default::default()
+        };
+        let status = service
+            .create_reservation(Request::new(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn batch_reports_lead_time_failures_with_the_single_create_code() {
+        let now = at(9, 0);
+        let service = service(lead_time_config(15), now);
+        let request = CreateReservationsRequest {
+            reservations: vec![ReservationRequest {
+                client_id: Uuid::new_v4().to_string(),
+                slot: Some(slot(
+                    now + Duration::minutes(14),
+                    now + Duration::minutes(74),
+                )),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let status = service
+            .create_reservations(Request::new(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert!(status.message().starts_with("Reservation at index 0: "));
+    }
+
+    #[tokio::test]
+    async fn lead_time_of_zero_accepts_any_start() {
+        let now = at(9, 0);
+
+        for config in [lead_time_config(0), ServiceConfig::default()] {
+            let service = service(config, now);
+            assert!(service.check_lead_time(now).is_ok());
+            assert!(service.check_lead_time(now - Duration::minutes(1)).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn max_advance_accepts_starts_up_to_the_limit() {
+        let now = at(9, 0);
+        let service = service(
+            ServiceConfig {
+                max_advance: Some(Duration::days(30)),
+                ..Default::default()
+            },
+            now,
+        );
+
+        let start = now + Duration::days(30);
+        assert!(service
+            .check_booking_horizon(start, start + Duration::hours(1))
+            .is_ok());
+
+        let start = start + Duration::minutes(1);
+        let status = service
+            .check_booking_horizon(start, start + Duration::hours(1))
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(status.message().contains("30 days"));
+    }
+
+    #[tokio::test]
+    async fn latest_bookable_end_takes_the_tighter_limit() {
+        let now = at(9, 0);
+        let config = |booking_horizon, max_advance| ServiceConfig {
+            booking_horizon,
+            max_advance,
+            ..Default::default()
+        };
+        let hour = Duration::hours(1);
+
+        let by_start = service(config(None, Some(Duration::days(30))), now);
+        assert_eq!(
+            by_start.latest_bookable_end(hour),
+            Some(now + Duration::days(30) + hour)
+        );
+
+        let by_end = service(
+            config(Some(Duration::days(10)), Some(Duration::days(30))),
+            now,
+        );
+        assert_eq!(
+            by_end.latest_bookable_end(hour),
+            Some(now + Duration::days(10))
+        );
+    }
+
+    #[tokio::test]
+    async fn max_advance_unset_is_unlimited() {
+        let now = at(9, 0);
+        let service = service(ServiceConfig::default(), now);
+
+        let start = now + Duration::days(3650);
+        assert!(service
+            .check_booking_horizon(start, start + Duration::hours(1))
+            .is_ok());
+        assert_eq!(service.latest_bookable_end(Duration::hours(1)), None);
+    }
+
+    #[tokio::test]
+    async fn require_admin_accepts_only_the_exact_token() {
+        let service = service(
+            ServiceConfig {
+                admin_token: Some("secret".to_string()),
+                ..Default::default()
+            },
+            at(9, 0),
+        );
+        let request = |token: Option<&str>| {
+            let mut request = Request::new(());
+            if let Some(token) = token {
+                request
+                    .metadata_mut()
+                    .insert(ADMIN_TOKEN_HEADER, token.parse().unwrap());
+            }
+            request
+        };
+
+        assert!(service.require_admin(&request(Some("secret"))).is_ok());
+        for token in [None, Some("secre"), Some("secret2"), Some("Secret")] {
+            let status = service.require_admin(&request(token)).unwrap_err();
+            assert_eq!(status.code(), Code::PermissionDenied);
+        }
+    }
+
+    #[test]
+    fn map_error_reports_clients_with_upcoming_reservations_as_failed_precondition() {
+        let id = Uuid::new_v4();
+        let status =
+            ReservationServiceImpl::map_error(RepositoryError::ClientHasUpcomingReservations {
+                id,
+                count: 2,
+            });
+
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert!(status.message().contains("has 2 upcoming reservations"));
+    }
+
+    #[test]
+    fn map_error_reports_lost_connections_as_unavailable() {
+        let errors = [
+            sqlx::Error::PoolTimedOut,
+            sqlx::Error::PoolClosed,
+            sqlx::Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionReset)),
+        ];
+
+        for err in errors {
+            let status = ReservationServiceImpl::map_error(RepositoryError::DatabaseError(err));
+            assert_eq!(status.code(), Code::Unavailable);
+        }
+    }
+
+    #[test]
+    fn map_error_reports_other_database_errors_as_internal() {
+        let errors = [
+            sqlx::Error::RowNotFound,
+            sqlx::Error::ColumnNotFound("attendee_client_ids".to_string()),
+        ];
+
+        for err in errors {
+            let status = ReservationServiceImpl::map_error(RepositoryError::DatabaseError(err));
+            assert_eq!(status.code(), Code::Internal);
+        }
+    }
+}
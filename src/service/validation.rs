@@ -0,0 +1,85 @@
+//! Checks on client supplied contact details
+
+/// Longest email address accepted, and longest part before the `@`, as limited by SMTP
+const MAX_EMAIL_LENGTH: usize = 254;
+const MAX_EMAIL_LOCAL_PART_LENGTH: usize = 64;
+
+/// Trim and lowercase an email so the same address is always stored the same way
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Check that an email has the shape `local@domain.tld` without whitespace and isn't
+/// longer than mail servers accept
+pub fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+
+    email.len() <= MAX_EMAIL_LENGTH
+        && !local.is_empty()
+        && local.len() <= MAX_EMAIL_LOCAL_PART_LENGTH
+        && !domain.contains('@')
+        && !email.chars().any(char::is_whitespace)
+        && domain
+            .split_once('.')
+            .is_some_and(|(name, tld)| !name.is_empty() && !tld.is_empty())
+        && !domain.ends_with('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_emails() {
+        for email in [
+            "alice@example.com",
+            "alice.smith+tag@mail.example.co.uk",
+            "a@b.io",
+        ] {
+            assert!(is_valid_email(email), "{email} should be valid");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_emails() {
+        for email in [
+            "",
+            "asdf",
+            "@example.com",
+            "alice@",
+            "alice@example",
+            "alice@.com",
+            "alice@example.",
+            "alice@@example.com",
+            "alice@exa@mple.com",
+            "alice smith@example.com",
+        ] {
+            assert!(!is_valid_email(email), "{email} should be invalid");
+        }
+    }
+
+    #[test]
+    fn rejects_over_length_emails() {
+        let local = "a".repeat(MAX_EMAIL_LOCAL_PART_LENGTH);
+        assert!(is_valid_email(&format!("{local}@example.com")));
+        assert!(!is_valid_email(&format!("{local}a@example.com")));
+
+        // Exactly at the overall limit, made up of 63 character labels
+        let domain = format!("{0}.{0}.{1}", "b".repeat(63), "c".repeat(61));
+        let email = format!("{local}@{domain}");
+        assert_eq!(email.len(), MAX_EMAIL_LENGTH);
+        assert!(is_valid_email(&email));
+        assert!(!is_valid_email(&format!("{local}@c{domain}")));
+    }
+
+    #[test]
+    fn normalize_email_trims_and_lowercases() {
+        assert_eq!(
+            normalize_email("  Alice.Smith@Example.COM \n"),
+            "alice.smith@example.com"
+        );
+        assert_eq!(normalize_email("alice@example.com"), "alice@example.com");
+    }
+}